@@ -916,8 +916,16 @@ class RenderLoop {
                 this.stop();
             }
             
-            // Create new ImageBuffer with new dimensions
-            this.imageBuffer = new ImageBuffer(tileWidth, tileHeight, numCols, numRows);
+            // Create new ImageBuffer with new dimensions (throws if the grid is too large)
+            try {
+                this.imageBuffer = new ImageBuffer(tileWidth, tileHeight, numCols, numRows);
+            } catch (error) {
+                alert(`Could not create the grid: ${error}`);
+                if (wasRunning) {
+                    this.start();
+                }
+                return;
+            }
             
             // Initialize background color in new WASM buffer
             this.imageBuffer.set_background_color(
@@ -1,4 +1,11 @@
-use image::{DynamicImage, GenericImageView};
+use image::codecs::gif::GifDecoder;
+use image::codecs::jpeg::{JpegDecoder, JpegEncoder};
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::{AnimationDecoder, DynamicImage, GenericImageView, ImageDecoder, ImageEncoder};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 
 #[derive(Clone)]
@@ -6,6 +13,142 @@ struct TileInfo {
     col: u32,
     row: u32,
     has_image: bool,
+    // Decoded source image kept so the tile can be re-composited without re-sending bytes
+    source: Option<Rc<DynamicImage>>,
+    scale: f32,
+    offset_x: i32,
+    offset_y: i32,
+    // Alpha multiplier applied since the last load; reset when new content arrives
+    opacity: f32,
+    // Per-tile background that replaces the global one when set; kept across loads and clears
+    background: Option<[u8; 4]>,
+    // Inner frame (thickness, color) painted over the tile after every composite
+    border: Option<(u32, [u8; 4])>,
+    // Tile-relative [x, y, width, height] covered by image pixels; None means the whole tile
+    drawn: Option<[u32; 4]>,
+    // Whether a filter or transform has changed the image since it was loaded
+    modified: bool,
+    // How offset redraws fill the part of the tile the image leaves uncovered
+    outside: OutsideMode,
+    // Locked tiles reject loads, clears and filters; kept across clears like other settings
+    locked: bool,
+}
+
+impl TileInfo {
+    fn empty(col: u32, row: u32) -> TileInfo {
+        TileInfo {
+            col,
+            row,
+            has_image: false,
+            source: None,
+            scale: 1.0,
+            offset_x: 0,
+            offset_y: 0,
+            opacity: 1.0,
+            background: None,
+            border: None,
+            drawn: None,
+            modified: false,
+            outside: OutsideMode::Background,
+            locked: false,
+        }
+    }
+
+    // Whether the tile carries settings worth keeping after its image is cleared
+    fn has_settings(&self) -> bool {
+        self.background.is_some() || self.border.is_some() || self.locked
+    }
+
+    // An entry for this position's settings carrying the image state of `other`
+    fn with_image_from(&self, other: &TileInfo) -> TileInfo {
+        TileInfo {
+            has_image: other.has_image,
+            source: other.source.clone(),
+            scale: other.scale,
+            offset_x: other.offset_x,
+            offset_y: other.offset_y,
+            opacity: other.opacity,
+            drawn: other.drawn,
+            modified: other.modified,
+            outside: other.outside,
+            ..self.cleared()
+        }
+    }
+
+    // An empty entry for the same position that keeps only the per-tile settings
+    fn cleared(&self) -> TileInfo {
+        TileInfo {
+            background: self.background,
+            border: self.border,
+            locked: self.locked,
+            ..TileInfo::empty(self.col, self.row)
+        }
+    }
+}
+
+// Saved form of the grid configuration and per-tile state, used by export/import_layout_json
+#[derive(Serialize, Deserialize)]
+struct LayoutJson {
+    tile_width: u32,
+    tile_height: u32,
+    num_cols: u32,
+    num_rows: u32,
+    #[serde(default)]
+    gutter: u32,
+    // Only needed for non-uniform grids; uniform ones repeat tile_width/tile_height
+    #[serde(default)]
+    col_widths: Option<Vec<u32>>,
+    #[serde(default)]
+    row_heights: Option<Vec<u32>>,
+    background: [u8; 4],
+    #[serde(default)]
+    letterbox: Option<[u8; 4]>,
+    #[serde(default)]
+    tiles: Vec<TileLayoutJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TileLayoutJson {
+    col: u32,
+    row: u32,
+    has_image: bool,
+    scale: f32,
+    offset_x: i32,
+    offset_y: i32,
+    opacity: f32,
+    background: Option<[u8; 4]>,
+    border: Option<(u32, [u8; 4])>,
+    #[serde(default)]
+    locked: bool,
+}
+
+// Failure for one entry of a load_batch manifest
+#[derive(Serialize)]
+struct BatchError {
+    index: usize,
+    col: u32,
+    row: u32,
+    error: String,
+}
+
+// Most undo snapshots kept; pushing past this drops the oldest
+const MAX_UNDO_SNAPSHOTS: usize = 10;
+
+// Saved buffer state for undo. Pixels are copied in full; tile sources are shared.
+struct Snapshot {
+    data: Vec<u8>,
+    loaded_tiles: Vec<TileInfo>,
+    premultiplied: bool,
+}
+
+// In-progress collage started by begin_tile_collage
+struct Collage {
+    col: u32,
+    row: u32,
+    sub_cols: u32,
+    sub_rows: u32,
+    // Index of the next sub-cell to fill, in row-major order
+    next: u32,
 }
 
 struct ProxyLoadParams {
@@ -17,6 +160,105 @@ struct ProxyLoadParams {
     offset_y: i32,
 }
 
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScaleMode {
+    // Fit the whole image inside the tile, leaving background bars
+    Fit,
+    // Cover the whole tile, cropping whatever overflows
+    Fill,
+    // Resize to exactly the tile dimensions, ignoring aspect ratio
+    Stretch,
+    // Repeat the image at its native size across the tile, clipping at the right and bottom
+    Repeat,
+    // Match the tile width; the height follows the aspect ratio and overflow is cropped
+    FitWidth,
+    // Match the tile height; the width follows the aspect ratio and overflow is cropped
+    FitHeight,
+    // Like Fit, but images that already fit keep their native size instead of being enlarged
+    DownscaleOnly,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResizeFilter {
+    // Fastest, blocky; good for live previews while dragging
+    Nearest,
+    // Bilinear
+    Triangle,
+    // Bicubic
+    CatmullRom,
+    // Sharpest and slowest; the default for final renders
+    Lanczos3,
+}
+
+impl ResizeFilter {
+    fn filter_type(self) -> image::imageops::FilterType {
+        match self {
+            ResizeFilter::Nearest => image::imageops::FilterType::Nearest,
+            ResizeFilter::Triangle => image::imageops::FilterType::Triangle,
+            ResizeFilter::CatmullRom => image::imageops::FilterType::CatmullRom,
+            ResizeFilter::Lanczos3 => image::imageops::FilterType::Lanczos3,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    // Where the image sits along each axis, in halves of the leftover space: 0 aligns the
+    // leading edges, 1 centers, 2 aligns the trailing edges
+    fn halves(self) -> (i32, i32) {
+        match self {
+            Anchor::TopLeft => (0, 0),
+            Anchor::Top => (1, 0),
+            Anchor::TopRight => (2, 0),
+            Anchor::Left => (0, 1),
+            Anchor::Center => (1, 1),
+            Anchor::Right => (2, 1),
+            Anchor::BottomLeft => (0, 2),
+            Anchor::Bottom => (1, 2),
+            Anchor::BottomRight => (2, 2),
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutsideMode {
+    // Fill with the tile's background (or letterbox) color
+    Background,
+    // Repeat the image's nearest edge pixels out to the tile edge
+    ClampEdge,
+    // Leave fully transparent pixels
+    Transparent,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PatternKind {
+    // Animated sine waves per channel
+    Waves,
+    // Gray checkerboard scrolling diagonally
+    Checkerboard,
+    // Diagonal color gradient drifting across the buffer
+    Gradient,
+    // Grayscale static, reseeded every frame
+    SolidNoise,
+}
+
 #[wasm_bindgen]
 pub struct ImageBuffer {
     width: u32,
@@ -25,8 +267,34 @@ pub struct ImageBuffer {
     tile_height: u32,
     num_cols: u32,
     num_rows: u32,
+    // Spacing in pixels between adjacent tiles
+    gutter: u32,
+    // Per-column widths and per-row heights, with the buffer offset where each one starts.
+    // Uniform grids repeat tile_width/tile_height; tile addressing always goes through these.
+    col_widths: Vec<u32>,
+    row_heights: Vec<u32>,
+    col_offsets: Vec<u32>,
+    row_offsets: Vec<u32>,
     data: Vec<u8>,
     loaded_tiles: Vec<TileInfo>,
+    // Bounding box [x0, y0, x1, y1) of pixels changed since the last take_dirty_rect
+    dirty: Option<[u32; 4]>,
+    // Optional downscaled mirror of `data` for fast previews; factor 0 means disabled.
+    // proxy_stale is the buffer region not yet folded into the proxy.
+    proxy_factor: u32,
+    proxy: Vec<u8>,
+    proxy_stale: Option<[u32; 4]>,
+    collage: Option<Collage>,
+    // Pixels covered by draw_grid_lines as (data index, original RGBA), plus the line color
+    grid_lines: Vec<(usize, [u8; 4])>,
+    grid_line_color: [u8; 4],
+    // Color for the bars around a fitted image that doesn't fill its tile; None uses the
+    // tile background
+    letterbox: Option<[u8; 4]>,
+    // Whether RGB is stored premultiplied by alpha; images loaded while set are premultiplied
+    // as they are composited so the whole buffer keeps one convention
+    premultiplied: bool,
+    undo_stack: Vec<Snapshot>,
     background_r: u8,
     background_g: u8,
     background_b: u8,
@@ -36,24 +304,55 @@ pub struct ImageBuffer {
 #[wasm_bindgen]
 impl ImageBuffer {
     #[wasm_bindgen(constructor)]
-    pub fn new(tile_width: u32, tile_height: u32, num_cols: u32, num_rows: u32) -> ImageBuffer {
-        let width = tile_width * num_cols;
-        let height = tile_height * num_rows;
-        let data = vec![0; (width * height * 4) as usize];
-        ImageBuffer {
-            width,
-            height,
-            tile_width,
-            tile_height,
-            num_cols,
-            num_rows,
-            data,
-            loaded_tiles: Vec::new(),
-            background_r: 255, // Default to white background
-            background_g: 255,
-            background_b: 255,
-            background_a: 255,
+    pub fn new(
+        tile_width: u32,
+        tile_height: u32,
+        num_cols: u32,
+        num_rows: u32,
+    ) -> Result<ImageBuffer, JsValue> {
+        ImageBuffer::from_layout(
+            vec![tile_width; num_cols as usize],
+            vec![tile_height; num_rows as usize],
+            0,
+        )
+    }
+
+    // Magazine-style grid where every column and row can have its own size
+    #[wasm_bindgen]
+    pub fn new_nonuniform(
+        col_widths: Vec<u32>,
+        row_heights: Vec<u32>,
+    ) -> Result<ImageBuffer, JsValue> {
+        ImageBuffer::from_layout(col_widths, row_heights, 0)
+    }
+
+    // Like `new`, but leaves `gutter` pixels of background between neighboring tiles
+    #[wasm_bindgen]
+    pub fn with_gutter(
+        tile_width: u32,
+        tile_height: u32,
+        num_cols: u32,
+        num_rows: u32,
+        gutter: u32,
+    ) -> Result<ImageBuffer, JsValue> {
+        let mut buffer = ImageBuffer::from_layout(
+            vec![tile_width; num_cols as usize],
+            vec![tile_height; num_rows as usize],
+            gutter,
+        )?;
+
+        // Gutters never receive image pixels, so paint them with the background up front
+        let background = buffer.stored_background();
+        for y in 0..buffer.height as usize {
+            for x in 0..buffer.width as usize {
+                if buffer.tile_at_pixel(x, y).is_none() {
+                    let index = (y * buffer.width as usize + x) * 4;
+                    buffer.data[index..index + 4].copy_from_slice(&background);
+                }
+            }
         }
+
+        Ok(buffer)
     }
 
     #[wasm_bindgen(getter)]
@@ -76,6 +375,21 @@ impl ImageBuffer {
         self.tile_height
     }
 
+    #[wasm_bindgen(getter)]
+    pub fn gutter(&self) -> u32 {
+        self.gutter
+    }
+
+    #[wasm_bindgen]
+    pub fn column_width(&self, col: u32) -> Option<u32> {
+        self.col_widths.get(col as usize).copied()
+    }
+
+    #[wasm_bindgen]
+    pub fn row_height(&self, row: u32) -> Option<u32> {
+        self.row_heights.get(row as usize).copied()
+    }
+
     #[wasm_bindgen]
     pub fn data_ptr(&self) -> *const u8 {
         self.data.as_ptr()
@@ -86,572 +400,7027 @@ impl ImageBuffer {
         self.data.len()
     }
 
+    // Owned copy of the whole RGBA buffer, for runtimes that can't view wasm memory directly
     #[wasm_bindgen]
-    pub fn set_background_color(&mut self, r: u8, g: u8, b: u8, a: u8) {
-        self.background_r = r;
-        self.background_g = g;
-        self.background_b = b;
-        self.background_a = a;
+    pub fn get_data(&self) -> Vec<u8> {
+        self.data.clone()
     }
 
-    // Helper method to check if a pixel is within any loaded tile
-    fn is_pixel_in_loaded_tile(&self, x: usize, y: usize) -> bool {
-        for tile_info in &self.loaded_tiles {
-            if tile_info.has_image {
-                let tile_start_x = (tile_info.col * self.tile_width) as usize;
-                let tile_start_y = (tile_info.row * self.tile_height) as usize;
-                let tile_end_x = tile_start_x + self.tile_width as usize;
-                let tile_end_y = tile_start_y + self.tile_height as usize;
-
-                if x >= tile_start_x && x < tile_end_x && y >= tile_start_y && y < tile_end_y {
-                    return true;
-                }
-            }
+    // Multiply every pixel's RGB by its alpha in place, for textures uploaded with
+    // `premultiplyAlpha: true`. Later loads follow suit until unpremultiply_alpha. Calling it
+    // on an already premultiplied buffer is a no-op.
+    #[wasm_bindgen]
+    pub fn premultiply_alpha(&mut self) {
+        if self.premultiplied {
+            return;
         }
-        false
+        self.data.chunks_exact_mut(4).for_each(premultiply_pixel);
+        self.premultiplied = true;
+        self.mark_dirty(0, 0, self.width, self.height);
     }
 
+    // Undo premultiply_alpha, dividing RGB back out by alpha. Fully transparent pixels stay
+    // black, and low alphas lose precision, so the round trip is close but not exact.
     #[wasm_bindgen]
-    pub fn generate_pattern(&mut self, frame: u32) {
-        let width = self.width as usize;
-        let height = self.height as usize;
+    pub fn unpremultiply_alpha(&mut self) {
+        if !self.premultiplied {
+            return;
+        }
+        self.data.chunks_exact_mut(4).for_each(unpremultiply_pixel);
+        self.premultiplied = false;
+        self.mark_dirty(0, 0, self.width, self.height);
+    }
 
-        for y in 0..height {
-            for x in 0..width {
-                // Skip pixels that are part of any loaded image
-                if self.is_pixel_in_loaded_tile(x, y) {
-                    continue;
-                }
+    #[wasm_bindgen]
+    pub fn is_alpha_premultiplied(&self) -> bool {
+        self.premultiplied
+    }
 
-                let index = (y * width + x) * 4;
+    // Keep a mirror of the buffer downscaled by `factor` (box-averaged) for preview uploads.
+    // A factor of 0 or 1 disables it.
+    #[wasm_bindgen]
+    pub fn enable_proxy(&mut self, factor: u32) {
+        if factor <= 1 {
+            self.proxy_factor = 0;
+            self.proxy = Vec::new();
+            self.proxy_stale = None;
+            return;
+        }
 
-                // Create a dynamic pattern based on frame number
-                let r = ((x as f32 + frame as f32 * 0.1).sin() * 127.0 + 128.0) as u8;
-                let g = ((y as f32 + frame as f32 * 0.15).sin() * 127.0 + 128.0) as u8;
-                let b = (((x + y) as f32 + frame as f32 * 0.2).sin() * 127.0 + 128.0) as u8;
-                let a = 255;
+        self.proxy_factor = factor;
+        self.proxy = vec![0; (self.proxy_width() * self.proxy_height() * 4) as usize];
+        self.proxy_stale = Some([0, 0, self.width, self.height]);
+    }
 
-                self.data[index] = r;
-                self.data[index + 1] = g;
-                self.data[index + 2] = b;
-                self.data[index + 3] = a;
-            }
+    #[wasm_bindgen(getter)]
+    pub fn proxy_width(&self) -> u32 {
+        self.width.div_ceil(self.proxy_factor.max(1))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn proxy_height(&self) -> u32 {
+        self.height.div_ceil(self.proxy_factor.max(1))
+    }
+
+    // Pointer to the proxy pixels, or to the full buffer when the proxy is disabled. Brings the
+    // proxy up to date with any changes since the last call first.
+    #[wasm_bindgen]
+    pub fn proxy_ptr(&mut self) -> *const u8 {
+        if self.proxy_factor == 0 {
+            return self.data_ptr();
         }
+        self.refresh_proxy();
+        self.proxy.as_ptr()
     }
 
     #[wasm_bindgen]
-    pub fn fill_background(&mut self) {
-        let width = self.width as usize;
-        let height = self.height as usize;
+    pub fn proxy_len(&self) -> usize {
+        if self.proxy_factor == 0 {
+            return self.data_len();
+        }
+        self.proxy.len()
+    }
 
-        for y in 0..height {
-            for x in 0..width {
-                // Skip pixels that are part of any loaded image
-                if self.is_pixel_in_loaded_tile(x, y) {
-                    continue;
-                }
+    // Helper method to re-average the proxy blocks overlapping the stale region
+    fn refresh_proxy(&mut self) {
+        let Some([x0, y0, x1, y1]) = self.proxy_stale.take() else {
+            return;
+        };
+        let factor = self.proxy_factor;
+        let proxy_width = self.proxy_width();
 
-                let index = (y * width + x) * 4;
+        for block_y in y0 / factor..y1.div_ceil(factor) {
+            for block_x in x0 / factor..x1.div_ceil(factor) {
+                let mut sum = [0u32; 4];
+                let mut count = 0;
+                for y in block_y * factor..((block_y + 1) * factor).min(self.height) {
+                    for x in block_x * factor..((block_x + 1) * factor).min(self.width) {
+                        let index = ((y * self.width + x) * 4) as usize;
+                        for (total, value) in sum.iter_mut().zip(&self.data[index..index + 4]) {
+                            *total += *value as u32;
+                        }
+                        count += 1;
+                    }
+                }
 
-                // Fill with solid background color
-                self.data[index] = self.background_r;
-                self.data[index + 1] = self.background_g;
-                self.data[index + 2] = self.background_b;
-                self.data[index + 3] = self.background_a;
+                let proxy_index = ((block_y * proxy_width + block_x) * 4) as usize;
+                for (channel, total) in sum.iter().enumerate() {
+                    self.proxy[proxy_index + channel] = ((total + count / 2) / count) as u8;
+                }
             }
         }
     }
 
     #[wasm_bindgen]
-    pub fn load_image_from_bytes(
-        &mut self,
-        image_data: &[u8],
-        col: u32,
-        row: u32,
-    ) -> Result<(), JsValue> {
-        self.load_image_from_bytes_with_scale(image_data, col, row, 1.0)
+    pub fn get_pixel(&self, x: u32, y: u32) -> Option<Vec<u8>> {
+        let index = self.pixel_index(x, y)?;
+        Some(self.data[index..index + 4].to_vec())
     }
 
     #[wasm_bindgen]
-    pub fn load_image_from_bytes_with_scale(
-        &mut self,
-        image_data: &[u8],
-        col: u32,
-        row: u32,
-        scale: f32,
-    ) -> Result<(), JsValue> {
-        // Validate tile position
-        if col >= self.num_cols || row >= self.num_rows {
-            return Err(JsValue::from_str(&format!(
-                "Invalid tile position ({}, {}). Grid is {}x{}",
-                col, row, self.num_cols, self.num_rows
-            )));
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_pixel(&mut self, x: u32, y: u32, r: u8, g: u8, b: u8, a: u8) {
+        // Out-of-bounds writes are silently ignored
+        if let Some(index) = self.pixel_index(x, y) {
+            self.data[index..index + 4].copy_from_slice(&[r, g, b, a]);
+            self.mark_dirty(x, y, 1, 1);
         }
-        let img = image::load_from_memory(image_data)
-            .map_err(|e| JsValue::from_str(&format!("Failed to decode image: {}", e)))?;
-
-        // Calculate scaled dimensions
-        let scaled_width = (self.tile_width as f32 * scale) as u32;
-        let scaled_height = (self.tile_height as f32 * scale) as u32;
-
-        let resized_img = resize_preserve_aspect_ratio(img, scaled_width, scaled_height);
-        let rgba_img = resized_img.to_rgba8();
-
-        // Get actual dimensions after aspect ratio preserving resize
-        let actual_width = rgba_img.width() as u32;
-        let actual_height = rgba_img.height() as u32;
-
-        // Calculate absolute position in the full buffer
-        let tile_start_x = (col * self.tile_width) as usize;
-        let tile_start_y = (row * self.tile_height) as usize;
+    }
 
-        // Remove any existing tile info for this position, then add new one
-        self.loaded_tiles
-            .retain(|tile| tile.col != col || tile.row != row);
-        self.loaded_tiles.push(TileInfo {
-            col,
-            row,
-            has_image: true,
-        });
+    // Paint-bucket fill from buffer pixel (x, y): every pixel connected to it (4-way) whose
+    // channels are all within `tolerance` of the starting pixel's color becomes the fill
+    // color. The fill can cross tile boundaries and gutters but stops at the buffer edge and
+    // never enters locked tiles; loaded tiles it paints count as modified. Returns the number
+    // of pixels filled; starting outside the buffer or in a locked tile fills nothing.
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn flood_fill(&mut self, x: u32, y: u32, r: u8, g: u8, b: u8, a: u8, tolerance: u8) -> u32 {
+        let Some(seed_index) = self.pixel_index(x, y) else {
+            return 0;
+        };
+        let seed: [u8; 4] = self.data[seed_index..seed_index + 4].try_into().unwrap();
+        let mut fill = [r, g, b, a];
+        self.match_buffer_alpha(&mut fill);
+        let (width, height) = (self.width as usize, self.height as usize);
 
-        // Calculate offsets for centering/cropping
-        let (src_offset_x, src_offset_y, dst_offset_x, dst_offset_y) = if scale >= 1.0 {
-            // Scale >= 100%: crop center of scaled image to fit tile
-            let crop_x = (actual_width.saturating_sub(self.tile_width)) / 2;
-            let crop_y = (actual_height.saturating_sub(self.tile_height)) / 2;
-            (crop_x, crop_y, 0, 0)
-        } else {
-            // Scale < 100%: center smaller image within tile
-            let center_x = (self.tile_width - actual_width) / 2;
-            let center_y = (self.tile_height - actual_height) / 2;
-            (0, 0, center_x, center_y)
+        // Pixels already filled, so a fill color that itself matches the seed can't loop, plus
+        // every pixel of a locked tile so the fill treats them as walls
+        let mut filled = vec![false; width * height];
+        for tile in self.loaded_tiles.iter().filter(|tile| tile.locked) {
+            for range in self.tile_row_ranges(tile.col, tile.row) {
+                filled[range.start / 4..range.end / 4].fill(true);
+            }
+        }
+        let mut touched = vec![false; (self.num_cols * self.num_rows) as usize];
+        let matches = |data: &[u8], filled: &[bool], index: usize| {
+            !filled[index]
+                && data[index * 4..index * 4 + 4]
+                    .iter()
+                    .zip(seed)
+                    .all(|(&channel, reference)| channel.abs_diff(reference) <= tolerance)
         };
 
-        // Clear the entire target tile area first
-        for y in 0..self.tile_height as usize {
-            for x in 0..self.tile_width as usize {
-                let dst_index = ((tile_start_y + y) * self.width as usize + (tile_start_x + x)) * 4;
+        let mut count = 0;
+        let [mut min_x, mut min_y, mut max_x, mut max_y] = [x as usize, y as usize, 0, 0];
+        let mut stack = vec![(x as usize, y as usize)];
+        while let Some((seed_x, span_y)) = stack.pop() {
+            let row_start = span_y * width;
+            if !matches(&self.data, &filled, row_start + seed_x) {
+                continue;
+            }
 
-                if dst_index + 3 < self.data.len() {
-                    // Calculate source coordinates
-                    let src_x = x as i32 - dst_offset_x as i32 + src_offset_x as i32;
-                    let src_y = y as i32 - dst_offset_y as i32 + src_offset_y as i32;
+            // Grow the span left and right from the popped pixel, then fill it
+            let mut left = seed_x;
+            while left > 0 && matches(&self.data, &filled, row_start + left - 1) {
+                left -= 1;
+            }
+            let mut right = seed_x;
+            while right + 1 < width && matches(&self.data, &filled, row_start + right + 1) {
+                right += 1;
+            }
+            let span = row_start + left..row_start + right + 1;
+            for pixel in self.data[span.start * 4..span.end * 4].chunks_exact_mut(4) {
+                pixel.copy_from_slice(&fill);
+            }
+            filled[span].fill(true);
+            for span_x in left..=right {
+                if let Some((col, row)) = self.tile_at_pixel(span_x, span_y) {
+                    touched[(row * self.num_cols + col) as usize] = true;
+                }
+            }
+            count += (right - left + 1) as u32;
+            min_x = min_x.min(left);
+            max_x = max_x.max(right);
+            min_y = min_y.min(span_y);
+            max_y = max_y.max(span_y);
 
-                    if src_x >= 0
-                        && src_y >= 0
-                        && src_x < actual_width as i32
-                        && src_y < actual_height as i32
-                    {
-                        // Copy pixel from image
-                        let pixel = rgba_img.get_pixel(src_x as u32, src_y as u32);
-                        self.data[dst_index] = pixel[0]; // R
-                        self.data[dst_index + 1] = pixel[1]; // G
-                        self.data[dst_index + 2] = pixel[2]; // B
-                        self.data[dst_index + 3] = pixel[3]; // A
-                    } else {
-                        // Background color for areas outside the image
-                        self.data[dst_index] = self.background_r;
-                        self.data[dst_index + 1] = self.background_g;
-                        self.data[dst_index + 2] = self.background_b;
-                        self.data[dst_index + 3] = self.background_a;
+            // Queue the start of every matching run in the rows above and below the span
+            let neighbours = [
+                span_y.checked_sub(1),
+                Some(span_y + 1).filter(|&ny| ny < height),
+            ];
+            for neighbour_y in neighbours.into_iter().flatten() {
+                let neighbour_start = neighbour_y * width;
+                let mut in_run = false;
+                for span_x in left..=right {
+                    let hit = matches(&self.data, &filled, neighbour_start + span_x);
+                    if hit && !in_run {
+                        stack.push((span_x, neighbour_y));
                     }
+                    in_run = hit;
                 }
             }
         }
 
-        Ok(())
+        if count > 0 {
+            self.mark_dirty(
+                min_x as u32,
+                min_y as u32,
+                (max_x - min_x + 1) as u32,
+                (max_y - min_y + 1) as u32,
+            );
+        }
+        for (index, _) in touched.iter().enumerate().filter(|(_, &hit)| hit) {
+            let index = index as u32;
+            self.mark_tile_modified(index % self.num_cols, index / self.num_cols);
+        }
+        count
     }
 
     #[wasm_bindgen]
-    pub fn load_image_from_bytes_with_scale_and_offset(
-        &mut self,
-        image_data: &[u8],
-        col: u32,
-        row: u32,
-        scale: f32,
-        offset_x: i32,
-        offset_y: i32,
-    ) -> Result<(), JsValue> {
-        // Validate tile position
-        if col >= self.num_cols || row >= self.num_rows {
-            return Err(JsValue::from_str(&format!(
-                "Invalid tile position ({}, {}). Grid is {}x{}",
-                col, row, self.num_cols, self.num_rows
-            )));
+    pub fn tile_at(&self, x: u32, y: u32) -> Option<Vec<u32>> {
+        // Returns [col, row], or None for gutters and coordinates outside the buffer
+        let (col, row) = self.tile_at_pixel(x as usize, y as usize)?;
+        Some(vec![col, row])
+    }
+
+    // Helper method to get the byte index of an absolute buffer coordinate
+    fn pixel_index(&self, x: u32, y: u32) -> Option<usize> {
+        if x >= self.width || y >= self.height {
+            return None;
         }
+        Some((y as usize * self.width as usize + x as usize) * 4)
+    }
 
-        let img = image::load_from_memory(image_data)
-            .map_err(|e| JsValue::from_str(&format!("Failed to decode image: {}", e)))?;
+    #[wasm_bindgen]
+    pub fn export_to_png(&self) -> Result<Vec<u8>, JsValue> {
+        encode_png(&self.data, self.width, self.height)
+    }
 
-        // Calculate scaled dimensions
-        let scaled_width = (self.tile_width as f32 * scale) as u32;
-        let scaled_height = (self.tile_height as f32 * scale) as u32;
+    #[wasm_bindgen]
+    pub fn export_tile_to_png(&self, col: u32, row: u32) -> Result<Vec<u8>, JsValue> {
+        // Validate tile position
+        self.validate_tile_position(col, row)?;
 
-        let resized_img = resize_preserve_aspect_ratio(img, scaled_width, scaled_height);
-        let rgba_img = resized_img.to_rgba8();
+        let (tile_width, tile_height) = self.tile_size(col, row);
+        let tile_pixels = self.read_tile_region(col, row);
+        encode_png(&tile_pixels, tile_width, tile_height)
+    }
 
-        // Get actual dimensions after aspect ratio preserving resize
-        let actual_width = rgba_img.width() as u32;
-        let actual_height = rgba_img.height() as u32;
+    // Copy one tile's packed RGBA pixels out of the buffer
+    #[wasm_bindgen]
+    pub fn get_tile_pixels(&self, col: u32, row: u32) -> Result<Vec<u8>, JsValue> {
+        // Validate tile position
+        self.validate_tile_position(col, row)?;
 
-        // Calculate absolute position in the full buffer
-        let tile_start_x = (col * self.tile_width) as usize;
-        let tile_start_y = (row * self.tile_height) as usize;
+        Ok(self.read_tile_region(col, row))
+    }
 
-        // Remove any existing tile info for this position, then add new one
-        self.loaded_tiles
-            .retain(|tile| tile.col != col || tile.row != row);
-        self.loaded_tiles.push(TileInfo {
-            col,
-            row,
-            has_image: true,
-        });
+    // FNV-1a hash of a tile's pixels, so clients can spot changed tiles and fetch only those
+    #[wasm_bindgen]
+    pub fn tile_checksum(&self, col: u32, row: u32) -> Result<u32, JsValue> {
+        // Validate tile position
+        self.validate_tile_position(col, row)?;
 
-        // Calculate positioning with user offset - use a unified approach for all scales
-        // Always position the image within the tile space, allowing offsets to move it around
-        let base_dst_x = if actual_width <= self.tile_width {
-            // Image is smaller or equal to tile - center it
-            (self.tile_width - actual_width) / 2
-        } else {
-            // Image is larger - no base destination offset, will crop from source
-            0
-        };
+        let mut hash: u32 = 0x811C_9DC5;
+        for range in self.tile_row_ranges(col, row) {
+            for &byte in &self.data[range] {
+                hash ^= byte as u32;
+                hash = hash.wrapping_mul(0x0100_0193);
+            }
+        }
+        Ok(hash)
+    }
 
-        let base_dst_y = if actual_height <= self.tile_height {
-            // Image is smaller or equal to tile - center it
-            (self.tile_height - actual_height) / 2
-        } else {
-            // Image is larger - no base destination offset, will crop from source
-            0
-        };
-
-        // Apply user offset to destination positioning
-        let dst_offset_x = (base_dst_x as i32 + offset_x)
-            .max(-(actual_width as i32))
-            .min(self.tile_width as i32) as u32;
-        let dst_offset_y = (base_dst_y as i32 + offset_y)
-            .max(-(actual_height as i32))
-            .min(self.tile_height as i32) as u32;
-
-        // Calculate source cropping if image extends beyond tile bounds
-        let src_offset_x = if actual_width > self.tile_width {
-            // Image is larger than tile - crop from center, adjusted by offset effect
-            let base_crop = (actual_width - self.tile_width) / 2;
-            (base_crop as i32 - offset_x)
-                .max(0)
-                .min((actual_width.saturating_sub(self.tile_width)) as i32) as u32
-        } else {
-            0
-        };
-
-        let src_offset_y = if actual_height > self.tile_height {
-            // Image is larger than tile - crop from center, adjusted by offset effect
-            let base_crop = (actual_height - self.tile_height) / 2;
-            (base_crop as i32 - offset_y)
-                .max(0)
-                .min((actual_height.saturating_sub(self.tile_height)) as i32) as u32
-        } else {
-            0
-        };
-
-        // Clear the entire target tile area first
-        for y in 0..self.tile_height as usize {
-            for x in 0..self.tile_width as usize {
-                let dst_index = ((tile_start_y + y) * self.width as usize + (tile_start_x + x)) * 4;
-
-                if dst_index + 3 < self.data.len() {
-                    // Calculate source coordinates
-                    let src_x = x as i32 - dst_offset_x as i32 + src_offset_x as i32;
-                    let src_y = y as i32 - dst_offset_y as i32 + src_offset_y as i32;
+    // Alpha-weighted average [r, g, b, a] over a tile, e.g. for palette swatches. Color channels
+    // are weighted by alpha so transparent pixels don't pull the color toward their stored RGB;
+    // a fully transparent tile reports its background color.
+    #[wasm_bindgen]
+    pub fn tile_average_color(&self, col: u32, row: u32) -> Result<Vec<u8>, JsValue> {
+        // Validate tile position
+        self.validate_tile_position(col, row)?;
 
-                    if src_x >= 0
-                        && src_y >= 0
-                        && src_x < actual_width as i32
-                        && src_y < actual_height as i32
-                    {
-                        // Copy pixel from image
-                        let pixel = rgba_img.get_pixel(src_x as u32, src_y as u32);
-                        self.data[dst_index] = pixel[0]; // R
-                        self.data[dst_index + 1] = pixel[1]; // G
-                        self.data[dst_index + 2] = pixel[2]; // B
-                        self.data[dst_index + 3] = pixel[3]; // A
-                    } else {
-                        // Background color for areas outside the image
-                        self.data[dst_index] = self.background_r;
-                        self.data[dst_index + 1] = self.background_g;
-                        self.data[dst_index + 2] = self.background_b;
-                        self.data[dst_index + 3] = self.background_a;
-                    }
+        let mut weighted = [0u64; 3];
+        let mut alpha_total = 0u64;
+        let mut pixel_count = 0u64;
+        for range in self.tile_row_ranges(col, row) {
+            for pixel in self.data[range].chunks_exact(4) {
+                let alpha = pixel[3] as u64;
+                for (sum, &channel) in weighted.iter_mut().zip(pixel) {
+                    *sum += channel as u64 * alpha;
                 }
+                alpha_total += alpha;
+                pixel_count += 1;
             }
         }
 
-        Ok(())
+        if alpha_total == 0 {
+            return Ok(self.tile_background(col, row).to_vec());
+        }
+        let average = |sum: u64, count: u64| ((sum + count / 2) / count) as u8;
+        Ok(vec![
+            average(weighted[0], alpha_total),
+            average(weighted[1], alpha_total),
+            average(weighted[2], alpha_total),
+            average(alpha_total, pixel_count),
+        ])
     }
 
+    // Per-channel histogram of a tile's pixels with nonzero alpha: 768 counts laid out as
+    // R[0..256], G[256..512], B[512..768], each bin indexed by channel value.
     #[wasm_bindgen]
-    pub fn clear_tile(&mut self, col: u32, row: u32) -> Result<(), JsValue> {
+    pub fn tile_histogram(&self, col: u32, row: u32) -> Result<Vec<u32>, JsValue> {
         // Validate tile position
-        if col >= self.num_cols || row >= self.num_rows {
-            return Err(JsValue::from_str(&format!(
-                "Invalid tile position ({}, {}). Grid is {}x{}",
-                col, row, self.num_cols, self.num_rows
-            )));
-        }
-
-        // Remove tile from loaded_tiles
-        self.loaded_tiles
-            .retain(|tile| tile.col != col || tile.row != row);
-
-        // Clear the tile area by setting it to transparent
-        let tile_start_x = (col * self.tile_width) as usize;
-        let tile_start_y = (row * self.tile_height) as usize;
+        self.validate_tile_position(col, row)?;
 
-        for y in 0..self.tile_height as usize {
-            for x in 0..self.tile_width as usize {
-                let dst_index = ((tile_start_y + y) * self.width as usize + (tile_start_x + x)) * 4;
-
-                if dst_index + 3 < self.data.len() {
-                    // Set to background color
-                    self.data[dst_index] = self.background_r; // R
-                    self.data[dst_index + 1] = self.background_g; // G
-                    self.data[dst_index + 2] = self.background_b; // B
-                    self.data[dst_index + 3] = self.background_a; // A
+        let mut bins = vec![0u32; 768];
+        for range in self.tile_row_ranges(col, row) {
+            for pixel in self.data[range]
+                .chunks_exact(4)
+                .filter(|pixel| pixel[3] > 0)
+            {
+                for (channel, &value) in pixel[..3].iter().enumerate() {
+                    bins[channel * 256 + value as usize] += 1;
                 }
             }
         }
-
-        Ok(())
+        Ok(bins)
     }
 
     #[wasm_bindgen]
-    pub fn is_tile_loaded(&self, col: u32, row: u32) -> bool {
-        self.loaded_tiles
-            .iter()
-            .any(|tile| tile.col == col && tile.row == row && tile.has_image)
+    pub fn export_to_jpeg(&self, quality: u8) -> Result<Vec<u8>, JsValue> {
+        // JPEG has no alpha channel, so composite onto the background color first
+        let rgb_data = self.flatten_onto_background();
+
+        let mut jpeg_bytes = Vec::new();
+        JpegEncoder::new_with_quality(&mut jpeg_bytes, quality.clamp(1, 100))
+            .encode(&rgb_data, self.width, self.height, image::ColorType::Rgb8)
+            .map_err(|e| JsValue::from_str(&format!("Failed to encode JPEG: {}", e)))?;
+        Ok(jpeg_bytes)
     }
 
+    // Lossless WebP keeps alpha and ignores quality. Lossy WebP (0-100 quality) flattens onto
+    // the background like JPEG and is only available with the `webp-lossy` feature.
     #[wasm_bindgen]
-    pub fn create_proxy_from_bytes(
-        &self,
-        image_data: &[u8],
-        scale: f32,
-    ) -> Result<Vec<u8>, JsValue> {
-        // Decode the image
-        let img = image::load_from_memory(image_data)
-            .map_err(|e| JsValue::from_str(&format!("Failed to decode image: {}", e)))?;
+    pub fn export_to_webp(&self, lossless: bool, quality: f32) -> Result<Vec<u8>, JsValue> {
+        if !lossless {
+            return self.export_to_lossy_webp(quality);
+        }
 
-        // Calculate scaled dimensions
-        let scaled_width = (self.tile_width as f32 * scale) as u32;
-        let scaled_height = (self.tile_height as f32 * scale) as u32;
+        let mut webp_bytes = Vec::new();
+        WebPEncoder::new_lossless(&mut webp_bytes)
+            .write_image(&self.data, self.width, self.height, image::ColorType::Rgba8)
+            .map_err(|e| JsValue::from_str(&format!("Failed to encode WebP: {}", e)))?;
+        Ok(webp_bytes)
+    }
 
-        // Resize with aspect ratio preservation
-        let resized_img = resize_preserve_aspect_ratio(img, scaled_width, scaled_height);
-        let rgba_img = resized_img.to_rgba8();
+    // image marks lossy WebP as deprecated, but it is the only lossy encoder it offers
+    #[cfg(feature = "webp-lossy")]
+    #[allow(deprecated)]
+    fn export_to_lossy_webp(&self, quality: f32) -> Result<Vec<u8>, JsValue> {
+        use image::codecs::webp::WebPQuality;
 
-        // Return the raw RGBA pixel data
-        Ok(rgba_img.into_raw())
+        let rgb_data = self.flatten_onto_background();
+        let quality = WebPQuality::lossy(quality.clamp(0.0, 100.0).round() as u8);
+
+        let mut webp_bytes = Vec::new();
+        WebPEncoder::new_with_quality(&mut webp_bytes, quality)
+            .write_image(&rgb_data, self.width, self.height, image::ColorType::Rgb8)
+            .map_err(|e| JsValue::from_str(&format!("Failed to encode WebP: {}", e)))?;
+        Ok(webp_bytes)
     }
 
-    #[wasm_bindgen]
-    pub fn get_proxy_dimensions(&self, image_data: &[u8], scale: f32) -> Result<Vec<u32>, JsValue> {
-        // Decode the image to get dimensions
-        let img = image::load_from_memory(image_data)
-            .map_err(|e| JsValue::from_str(&format!("Failed to decode image: {}", e)))?;
+    #[cfg(not(feature = "webp-lossy"))]
+    fn export_to_lossy_webp(&self, _quality: f32) -> Result<Vec<u8>, JsValue> {
+        Err(JsValue::from_str(
+            "Lossy WebP export requires the webp-lossy feature",
+        ))
+    }
 
-        // Calculate scaled dimensions
-        let scaled_width = (self.tile_width as f32 * scale) as u32;
-        let scaled_height = (self.tile_height as f32 * scale) as u32;
+    // Helper method to blend every pixel over the opaque background color, dropping alpha
+    fn flatten_onto_background(&self) -> Vec<u8> {
+        let background = [self.background_r, self.background_g, self.background_b];
+        let mut rgb_data = Vec::with_capacity(self.data.len() / 4 * 3);
 
-        // Get the actual dimensions after aspect ratio preserving resize
-        let resized_img = resize_preserve_aspect_ratio(img, scaled_width, scaled_height);
+        for pixel in self.data.chunks_exact(4) {
+            let alpha = pixel[3] as u32;
+            for channel in 0..3 {
+                let blended = (pixel[channel] as u32 * alpha
+                    + background[channel] as u32 * (255 - alpha))
+                    / 255;
+                rgb_data.push(blended as u8);
+            }
+        }
+        rgb_data
+    }
 
-        // Return [width, height]
-        Ok(vec![resized_img.width(), resized_img.height()])
+    #[wasm_bindgen]
+    pub fn set_background_color(&mut self, r: u8, g: u8, b: u8, a: u8) {
+        self.background_r = r;
+        self.background_g = g;
+        self.background_b = b;
+        self.background_a = a;
     }
 
+    // Use a dedicated color for the bars around fitted images instead of the background
     #[wasm_bindgen]
-    pub fn get_original_dimensions(&self, image_data: &[u8]) -> Result<Vec<u32>, JsValue> {
-        // Decode the image to get original dimensions
-        let img = image::load_from_memory(image_data)
-            .map_err(|e| JsValue::from_str(&format!("Failed to decode image: {}", e)))?;
+    pub fn set_letterbox_color(&mut self, r: u8, g: u8, b: u8, a: u8) {
+        self.letterbox = Some([r, g, b, a]);
+    }
 
-        // Get the original dimensions without any scaling or resizing
-        let (original_width, original_height) = img.dimensions();
+    // Go back to filling letterbox bars with the tile background
+    #[wasm_bindgen]
+    pub fn clear_letterbox_color(&mut self) {
+        self.letterbox = None;
+    }
 
-        // Return [width, height]
-        Ok(vec![original_width, original_height])
+    // Global background as [r, g, b, a]
+    #[wasm_bindgen(getter)]
+    pub fn background_color(&self) -> Vec<u8> {
+        vec![
+            self.background_r,
+            self.background_g,
+            self.background_b,
+            self.background_a,
+        ]
     }
 
     #[wasm_bindgen]
-    #[allow(clippy::too_many_arguments)]
-    pub fn load_rgba_proxy_with_offset(
+    pub fn set_tile_background_color(
         &mut self,
-        rgba_data: &[u8],
-        proxy_width: u32,
-        proxy_height: u32,
         col: u32,
         row: u32,
-        offset_x: i32,
-        offset_y: i32,
+        r: u8,
+        g: u8,
+        b: u8,
+        a: u8,
     ) -> Result<(), JsValue> {
-        let params = ProxyLoadParams {
-            proxy_width,
-            proxy_height,
-            col,
-            row,
-            offset_x,
-            offset_y,
-        };
-        self.load_rgba_proxy_internal(rgba_data, params)
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+
+        self.tile_info_mut(col, row).background = Some([r, g, b, a]);
+
+        Ok(())
     }
 
-    fn load_rgba_proxy_internal(
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_tile_border(
         &mut self,
-        rgba_data: &[u8],
-        params: ProxyLoadParams,
+        col: u32,
+        row: u32,
+        thickness: u32,
+        r: u8,
+        g: u8,
+        b: u8,
+        a: u8,
     ) -> Result<(), JsValue> {
         // Validate tile position
-        if params.col >= self.num_cols || params.row >= self.num_rows {
-            return Err(JsValue::from_str(&format!(
-                "Invalid tile position ({}, {}). Grid is {}x{}",
-                params.col, params.row, self.num_cols, self.num_rows
-            )));
-        }
-
-        // Validate RGBA data length
-        let expected_len = (params.proxy_width * params.proxy_height * 4) as usize;
-        if rgba_data.len() != expected_len {
-            return Err(JsValue::from_str(&format!(
-                "Invalid RGBA data length. Expected {}, got {}",
-                expected_len,
-                rgba_data.len()
-            )));
-        }
+        self.validate_tile_unlocked(col, row)?;
 
-        // Calculate absolute position in the full buffer
-        let tile_start_x = (params.col * self.tile_width) as usize;
-        let tile_start_y = (params.row * self.tile_height) as usize;
+        // A zero thickness only forgets the border; pixels already painted stay until the
+        // tile is next composited
+        self.tile_info_mut(col, row).border = if thickness == 0 {
+            None
+        } else {
+            Some((thickness, [r, g, b, a]))
+        };
+        self.draw_tile_border(col, row);
 
-        // Remove any existing tile info for this position, then add new one
-        self.loaded_tiles
-            .retain(|tile| tile.col != params.col || tile.row != params.row);
-        self.loaded_tiles.push(TileInfo {
-            col: params.col,
-            row: params.row,
-            has_image: true,
-        });
+        Ok(())
+    }
 
-        // Calculate offsets for positioning the proxy image within the tile
-        let center_x = (self.tile_width as i32 - params.proxy_width as i32) / 2;
-        let center_y = (self.tile_height as i32 - params.proxy_height as i32) / 2;
+    // Helper method to apply a per-pixel operation to every RGBA pixel in a tile
+    fn for_each_tile_pixel(&mut self, col: u32, row: u32, mut op: impl FnMut(&mut [u8])) {
+        self.mark_tile_dirty(col, row);
+        for range in self.tile_row_ranges(col, row) {
+            for pixel in self.data[range].chunks_exact_mut(4) {
+                op(pixel);
+            }
+        }
+    }
 
-        // Clear the entire target tile area first
-        for y in 0..self.tile_height as usize {
-            for x in 0..self.tile_width as usize {
-                let dst_index = ((tile_start_y + y) * self.width as usize + (tile_start_x + x)) * 4;
+    // Helper method to paint a tile's stored border, if any, along the inside of its edges
+    fn draw_tile_border(&mut self, col: u32, row: u32) {
+        let border = self.tile_info(col, row).and_then(|tile| tile.border);
+        let Some((thickness, mut color)) = border else {
+            return;
+        };
+        self.match_buffer_alpha(&mut color);
 
-                if dst_index + 3 < self.data.len() {
-                    // Calculate source coordinates with offsets
-                    let src_x = x as i32 - center_x - params.offset_x;
-                    let src_y = y as i32 - center_y - params.offset_y;
+        self.mark_tile_dirty(col, row);
+        let (tile_start_x, tile_start_y) = self.tile_origin(col, row);
+        let (tile_width, tile_height) = self.tile_size(col, row);
+        let (tile_width, tile_height) = (tile_width as usize, tile_height as usize);
+        let thickness = thickness as usize;
 
-                    if src_x >= 0
-                        && src_y >= 0
-                        && src_x < params.proxy_width as i32
-                        && src_y < params.proxy_height as i32
-                    {
-                        // Copy pixel from proxy RGBA data
-                        let src_index =
-                            ((src_y as u32 * params.proxy_width + src_x as u32) * 4) as usize;
-                        self.data[dst_index] = rgba_data[src_index]; // R
-                        self.data[dst_index + 1] = rgba_data[src_index + 1]; // G
-                        self.data[dst_index + 2] = rgba_data[src_index + 2]; // B
-                        self.data[dst_index + 3] = rgba_data[src_index + 3]; // A
-                    } else {
-                        // Background color for areas outside the proxy image
-                        self.data[dst_index] = self.background_r;
-                        self.data[dst_index + 1] = self.background_g;
-                        self.data[dst_index + 2] = self.background_b;
-                        self.data[dst_index + 3] = self.background_a;
-                    }
+        for y in 0..tile_height {
+            for x in 0..tile_width {
+                let on_border = x < thickness
+                    || y < thickness
+                    || x + thickness >= tile_width
+                    || y + thickness >= tile_height;
+                if on_border {
+                    let index = ((tile_start_y + y) * self.width as usize + tile_start_x + x) * 4;
+                    self.data[index..index + 4].copy_from_slice(&color);
                 }
             }
         }
+    }
 
-        Ok(())
+    // Returns the [x, y, width, height] bounding box of pixels changed since the last call, then
+    // resets it. None means nothing changed.
+    #[wasm_bindgen]
+    pub fn take_dirty_rect(&mut self) -> Option<Vec<u32>> {
+        self.dirty
+            .take()
+            .map(|[x0, y0, x1, y1]| vec![x0, y0, x1 - x0, y1 - y0])
     }
-}
 
-fn resize_preserve_aspect_ratio(
-    img: DynamicImage,
-    target_width: u32,
-    target_height: u32,
-) -> DynamicImage {
-    let (original_width, original_height) = img.dimensions();
+    // Helper method to grow the dirty bounding box to include a rectangle
+    fn mark_dirty(&mut self, x: u32, y: u32, width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        let rect = [x, y, x + width, y + height];
+        self.dirty = Some(union_rect(self.dirty, rect));
+        if self.proxy_factor > 0 {
+            self.proxy_stale = Some(union_rect(self.proxy_stale, rect));
+        }
+    }
 
-    // Calculate scaling factor to fit within target dimensions while preserving aspect ratio
-    let scale_x = target_width as f32 / original_width as f32;
-    let scale_y = target_height as f32 / original_height as f32;
-    let scale = scale_x.min(scale_y);
+    // Helper method to add a whole tile to the dirty bounding box
+    fn mark_tile_dirty(&mut self, col: u32, row: u32) {
+        let (tile_start_x, tile_start_y) = self.tile_origin(col, row);
+        let (tile_width, tile_height) = self.tile_size(col, row);
+        self.mark_dirty(
+            tile_start_x as u32,
+            tile_start_y as u32,
+            tile_width,
+            tile_height,
+        );
+    }
 
-    let new_width = (original_width as f32 * scale) as u32;
-    let new_height = (original_height as f32 * scale) as u32;
+    // Shared constructor: builds the offset tables and buffer for the given column widths,
+    // row heights and gutter. tile_width/tile_height report the first column and row.
+    fn from_layout(
+        col_widths: Vec<u32>,
+        row_heights: Vec<u32>,
+        gutter: u32,
+    ) -> Result<ImageBuffer, JsValue> {
+        // Reject layouts whose pixel dimensions or byte length don't fit, instead of wrapping
+        let too_large = || {
+            JsValue::from_str(&format!(
+                "Requested buffer too large: {} columns x {} rows",
+                col_widths.len(),
+                row_heights.len()
+            ))
+        };
+        let width = layout_extent(&col_widths, gutter).ok_or_else(too_large)?;
+        let height = layout_extent(&row_heights, gutter).ok_or_else(too_large)?;
+        let data_len = buffer_len(width, height).ok_or_else(too_large)?;
 
-    img.resize(new_width, new_height, image::imageops::FilterType::Lanczos3)
-}
+        let col_offsets = layout_offsets(&col_widths, gutter);
+        let row_offsets = layout_offsets(&row_heights, gutter);
+        let data = vec![0; data_len];
+        Ok(ImageBuffer {
+            width,
+            height,
+            tile_width: col_widths.first().copied().unwrap_or(0),
+            tile_height: row_heights.first().copied().unwrap_or(0),
+            num_cols: col_widths.len() as u32,
+            num_rows: row_heights.len() as u32,
+            gutter,
+            col_widths,
+            row_heights,
+            col_offsets,
+            row_offsets,
+            data,
+            loaded_tiles: Vec::new(),
+            dirty: None,
+            proxy_factor: 0,
+            proxy: Vec::new(),
+            proxy_stale: None,
+            collage: None,
+            grid_lines: Vec::new(),
+            grid_line_color: [0; 4],
+            letterbox: None,
+            premultiplied: false,
+            undo_stack: Vec::new(),
+            background_r: 255, // Default to white background
+            background_g: 255,
+            background_b: 255,
+            background_a: 255,
+        })
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use wasm_bindgen_test::*;
+    // Helper method to get the buffer coordinates of a tile's top-left pixel
+    fn tile_origin(&self, col: u32, row: u32) -> (usize, usize) {
+        (
+            self.col_offsets[col as usize] as usize,
+            self.row_offsets[row as usize] as usize,
+        )
+    }
 
-    wasm_bindgen_test_configure!(run_in_browser);
+    // Helper method to get a tile's width and height
+    fn tile_size(&self, col: u32, row: u32) -> (u32, u32) {
+        (
+            self.col_widths[col as usize],
+            self.row_heights[row as usize],
+        )
+    }
 
-    #[wasm_bindgen_test]
-    fn test_image_buffer_creation() {
-        let buffer = ImageBuffer::new(100, 100, 2, 2);
-        assert_eq!(buffer.width(), 200);
-        assert_eq!(buffer.height(), 200);
-        assert_eq!(buffer.tile_width(), 100);
-        assert_eq!(buffer.tile_height(), 100);
-        assert_eq!(buffer.data_len(), 200 * 200 * 4);
+    // Helper method to get the byte range of each pixel row of a tile within `data`
+    fn tile_row_ranges(&self, col: u32, row: u32) -> impl Iterator<Item = Range<usize>> {
+        let (tile_start_x, tile_start_y) = self.tile_origin(col, row);
+        let (tile_width, tile_height) = self.tile_size(col, row);
+        let buffer_width = self.width as usize;
+        let row_len = tile_width as usize * 4;
+
+        (0..tile_height as usize).map(move |y| {
+            let start = ((tile_start_y + y) * buffer_width + tile_start_x) * 4;
+            start..start + row_len
+        })
     }
 
-    #[wasm_bindgen_test]
-    fn test_pattern_generation() {
-        let mut buffer = ImageBuffer::new(10, 10, 1, 1);
-        buffer.generate_pattern(0);
+    // Helper method to find the tile containing a buffer pixel; None for gutters and
+    // coordinates outside the grid
+    fn tile_at_pixel(&self, x: usize, y: usize) -> Option<(u32, u32)> {
+        let col = layout_index(&self.col_offsets, &self.col_widths, x)?;
+        let row = layout_index(&self.row_offsets, &self.row_heights, y)?;
+        Some((col, row))
+    }
 
-        // Check that data has been populated (not all zeros)
-        let data_ptr = buffer.data_ptr();
-        let data_len = buffer.data_len();
+    // Helper method to reject tile coordinates outside the grid
+    fn validate_tile_position(&self, col: u32, row: u32) -> Result<(), JsValue> {
+        if col >= self.num_cols || row >= self.num_rows {
+            return Err(JsValue::from_str(&format!(
+                "Invalid tile position ({}, {}). Grid is {}x{}",
+                col, row, self.num_cols, self.num_rows
+            )));
+        }
+        Ok(())
+    }
 
-        // This is a basic test - in practice we'd check specific pattern values
-        assert!(data_len > 0);
-        assert!(!data_ptr.is_null());
+    // Helper method to reject tile coordinates outside the grid or naming a locked tile, for
+    // operations that would change the tile's pixels or image
+    fn validate_tile_unlocked(&self, col: u32, row: u32) -> Result<(), JsValue> {
+        self.validate_tile_position(col, row)?;
+        if self.is_tile_locked(col, row) {
+            return Err(JsValue::from_str(&format!(
+                "Tile ({}, {}) is locked",
+                col, row
+            )));
+        }
+        Ok(())
     }
 
-    #[wasm_bindgen_test]
-    fn test_resize_preserve_aspect_ratio() {
-        // Create a simple 2x1 test image (landscape)
-        let img = DynamicImage::new_rgb8(200, 100);
-        let resized = resize_preserve_aspect_ratio(img, 100, 100);
+    // Helper method to reject scale factors that would resize an image to nothing or to an
+    // unreasonable size
+    fn validate_scale(scale: f32) -> Result<(), JsValue> {
+        check_scale(scale).map_err(|e| JsValue::from_str(&e))
+    }
 
-        // Should fit within 100x100, maintaining aspect ratio
-        let (w, h) = resized.dimensions();
-        assert_eq!(w, 100); // Full width used
-        assert_eq!(h, 50); // Height scaled proportionally
+    // Helper method to reject pixel transfers between tiles of different sizes, which can
+    // only happen in non-uniform grids
+    fn validate_same_tile_size(
+        &self,
+        col_a: u32,
+        row_a: u32,
+        col_b: u32,
+        row_b: u32,
+    ) -> Result<(), JsValue> {
+        let size_a = self.tile_size(col_a, row_a);
+        let size_b = self.tile_size(col_b, row_b);
+        if size_a != size_b {
+            return Err(JsValue::from_str(&format!(
+                "Tiles ({}, {}) and ({}, {}) differ in size: {}x{} vs {}x{}",
+                col_a, row_a, col_b, row_b, size_a.0, size_a.1, size_b.0, size_b.1
+            )));
+        }
+        Ok(())
     }
 
-    #[test]
-    fn test_image_buffer_dimensions() {
-        let buffer = ImageBuffer::new(50, 75, 3, 4);
-        assert_eq!(buffer.width, 150); // 50 * 3
-        assert_eq!(buffer.height, 300); // 75 * 4
-        assert_eq!(buffer.tile_width, 50);
-        assert_eq!(buffer.tile_height, 75);
-        assert_eq!(buffer.num_cols, 3);
-        assert_eq!(buffer.num_rows, 4);
+    // Helper method to copy a tile's region out of the buffer as packed RGBA
+    fn read_tile_region(&self, col: u32, row: u32) -> Vec<u8> {
+        let (tile_width, tile_height) = self.tile_size(col, row);
+
+        let mut pixels = Vec::with_capacity((tile_width * tile_height * 4) as usize);
+        for range in self.tile_row_ranges(col, row) {
+            pixels.extend_from_slice(&self.data[range]);
+        }
+        pixels
+    }
+
+    // Helper method to write packed RGBA pixels back over a tile's region
+    fn write_tile_region(&mut self, col: u32, row: u32, pixels: &[u8]) {
+        self.mark_tile_dirty(col, row);
+        let row_len = self.tile_size(col, row).0 as usize * 4;
+
+        for (range, src_row) in self
+            .tile_row_ranges(col, row)
+            .zip(pixels.chunks_exact(row_len))
+        {
+            self.data[range].copy_from_slice(src_row);
+        }
+    }
+
+    // Helper method to replace any existing tile info for this position with a loaded entry
+    fn mark_tile_loaded(&mut self, col: u32, row: u32) -> &mut TileInfo {
+        let mut tile_info = self
+            .take_tile_info(col, row)
+            .map(|previous| previous.cleared())
+            .unwrap_or_else(|| TileInfo::empty(col, row));
+        tile_info.has_image = true;
+        self.loaded_tiles.push(tile_info);
+        self.loaded_tiles.last_mut().unwrap()
+    }
+
+    // Helper method to look up the tile info for a position
+    fn tile_info(&self, col: u32, row: u32) -> Option<&TileInfo> {
+        self.loaded_tiles
+            .iter()
+            .find(|tile| tile.col == col && tile.row == row)
+    }
+
+    // Helper method to get the tile info for a position, adding an empty entry if missing
+    fn tile_info_mut(&mut self, col: u32, row: u32) -> &mut TileInfo {
+        match self
+            .loaded_tiles
+            .iter()
+            .position(|tile| tile.col == col && tile.row == row)
+        {
+            Some(index) => &mut self.loaded_tiles[index],
+            None => {
+                self.loaded_tiles.push(TileInfo::empty(col, row));
+                self.loaded_tiles.last_mut().unwrap()
+            }
+        }
+    }
+
+    // Helper method to flag the tile's loaded image, if any, as changed since it was loaded
+    fn mark_tile_modified(&mut self, col: u32, row: u32) {
+        if let Some(tile_info) = self
+            .loaded_tiles
+            .iter_mut()
+            .find(|tile| tile.col == col && tile.row == row && tile.has_image)
+        {
+            tile_info.modified = true;
+        }
+    }
+
+    // Helper method to remove and return the tile info for a position, if any
+    fn take_tile_info(&mut self, col: u32, row: u32) -> Option<TileInfo> {
+        let index = self
+            .loaded_tiles
+            .iter()
+            .position(|tile| tile.col == col && tile.row == row)?;
+        Some(self.loaded_tiles.remove(index))
+    }
+
+    // Helper method to get the background color for a tile, honoring any per-tile override
+    fn tile_background(&self, col: u32, row: u32) -> [u8; 4] {
+        self.tile_info(col, row)
+            .and_then(|tile| tile.background)
+            .unwrap_or([
+                self.background_r,
+                self.background_g,
+                self.background_b,
+                self.background_a,
+            ])
+    }
+
+    // Helper method to get the global background color in the buffer's alpha convention, ready
+    // to be written into the buffer
+    fn stored_background(&self) -> [u8; 4] {
+        let mut background = [
+            self.background_r,
+            self.background_g,
+            self.background_b,
+            self.background_a,
+        ];
+        self.match_buffer_alpha(&mut background);
+        background
+    }
+
+    // Helper method to get tile_background in the buffer's alpha convention
+    fn stored_tile_background(&self, col: u32, row: u32) -> [u8; 4] {
+        let mut background = self.tile_background(col, row);
+        self.match_buffer_alpha(&mut background);
+        background
+    }
+
+    // Helper method to fill a tile's whole region with a single straight-alpha color
+    fn fill_tile_region(&mut self, col: u32, row: u32, mut color: [u8; 4]) {
+        self.match_buffer_alpha(&mut color);
+        self.for_each_tile_pixel(col, row, |pixel| pixel.copy_from_slice(&color));
+    }
+
+    // Helper method to pick the source-over blend matching the buffer's alpha convention
+    fn source_over_fn(&self) -> fn(&[u8], &[u8]) -> [u8; 4] {
+        if self.premultiplied {
+            source_over_premultiplied
+        } else {
+            source_over
+        }
+    }
+
+    // Helper method to bring freshly decoded or generated straight-alpha RGBA pixels into the
+    // buffer's convention, premultiplying them while premultiply_alpha is in effect. Pixels
+    // read back from the buffer are already converted and must not pass through again.
+    fn match_buffer_alpha(&self, pixels: &mut [u8]) {
+        if self.premultiplied {
+            pixels.chunks_exact_mut(4).for_each(premultiply_pixel);
+        }
+    }
+
+    // Helper method to draw packed RGBA pixels into a tile. The source's top-left corner
+    // lands at (origin_x, origin_y) relative to the tile, so negative origins crop it.
+    // Tile pixels not covered by the source are set to the letterbox or background color.
+    // Returns the [x, y, width, height] buffer rectangle covered by source pixels.
+    #[allow(clippy::too_many_arguments)]
+    fn composite_into_tile(
+        &mut self,
+        col: u32,
+        row: u32,
+        src_data: &[u8],
+        src_width: u32,
+        src_height: u32,
+        origin_x: i32,
+        origin_y: i32,
+    ) -> [u32; 4] {
+        self.composite_into_tile_with_outside(
+            col,
+            row,
+            src_data,
+            src_width,
+            src_height,
+            origin_x,
+            origin_y,
+            OutsideMode::Background,
+        )
+    }
+
+    // composite_into_tile, filling the pixels the source doesn't cover as `outside` says
+    #[allow(clippy::too_many_arguments)]
+    fn composite_into_tile_with_outside(
+        &mut self,
+        col: u32,
+        row: u32,
+        src_data: &[u8],
+        src_width: u32,
+        src_height: u32,
+        origin_x: i32,
+        origin_y: i32,
+        outside: OutsideMode,
+    ) -> [u32; 4] {
+        self.mark_tile_dirty(col, row);
+        let (tile_start_x, tile_start_y) = self.tile_origin(col, row);
+        let (tile_width, tile_height) = self.tile_size(col, row);
+        let mut background = self
+            .letterbox
+            .unwrap_or_else(|| self.tile_background(col, row));
+        self.match_buffer_alpha(&mut background);
+
+        for y in 0..tile_height as usize {
+            for x in 0..tile_width as usize {
+                let dst_index = ((tile_start_y + y) * self.width as usize + (tile_start_x + x)) * 4;
+
+                // dst_index + 3 is the pixel's last byte, so `<` (not `<=`) admits the final pixel
+                if dst_index + 3 < self.data.len() {
+                    // Calculate source coordinates
+                    let src_x = x as i32 - origin_x;
+                    let src_y = y as i32 - origin_y;
+
+                    if src_x >= 0
+                        && src_y >= 0
+                        && src_x < src_width as i32
+                        && src_y < src_height as i32
+                    {
+                        // Copy pixel from source
+                        let src_index = ((src_y as u32 * src_width + src_x as u32) * 4) as usize;
+                        self.data[dst_index] = src_data[src_index]; // R
+                        self.data[dst_index + 1] = src_data[src_index + 1]; // G
+                        self.data[dst_index + 2] = src_data[src_index + 2]; // B
+                        self.data[dst_index + 3] = src_data[src_index + 3]; // A
+                    } else if outside == OutsideMode::ClampEdge && src_width > 0 && src_height > 0 {
+                        // Repeat the nearest edge pixel of the source
+                        let edge_x = src_x.clamp(0, src_width as i32 - 1) as u32;
+                        let edge_y = src_y.clamp(0, src_height as i32 - 1) as u32;
+                        let src_index = ((edge_y * src_width + edge_x) * 4) as usize;
+                        self.data[dst_index..dst_index + 4]
+                            .copy_from_slice(&src_data[src_index..src_index + 4]);
+                    } else if outside == OutsideMode::Transparent {
+                        self.data[dst_index..dst_index + 4].fill(0);
+                    } else {
+                        // Background color for areas outside the source
+                        self.data[dst_index..dst_index + 4].copy_from_slice(&background);
+                    }
+                }
+            }
+        }
+
+        // Keep any configured frame on top of the new content
+        self.draw_tile_border(col, row);
+
+        let [draw_x, draw_y, draw_width, draw_height] =
+            self.record_drawn_rect(col, row, src_width, src_height, origin_x, origin_y);
+        [
+            tile_start_x as u32 + draw_x,
+            tile_start_y as u32 + draw_y,
+            draw_width,
+            draw_height,
+        ]
+    }
+
+    // Helper method to store, on the tile's loaded entry, the tile-relative rectangle a source
+    // placed at (origin_x, origin_y) covers, and return it
+    fn record_drawn_rect(
+        &mut self,
+        col: u32,
+        row: u32,
+        src_width: u32,
+        src_height: u32,
+        origin_x: i32,
+        origin_y: i32,
+    ) -> [u32; 4] {
+        let (tile_width, tile_height) = self.tile_size(col, row);
+
+        // Intersect the placed source with the tile to find the drawn region
+        let draw_x0 = origin_x.clamp(0, tile_width as i32);
+        let draw_y0 = origin_y.clamp(0, tile_height as i32);
+        let draw_x1 = (origin_x + src_width as i32).clamp(draw_x0, tile_width as i32);
+        let draw_y1 = (origin_y + src_height as i32).clamp(draw_y0, tile_height as i32);
+        let drawn = [
+            draw_x0 as u32,
+            draw_y0 as u32,
+            (draw_x1 - draw_x0) as u32,
+            (draw_y1 - draw_y0) as u32,
+        ];
+
+        if let Some(tile_info) = self
+            .loaded_tiles
+            .iter_mut()
+            .find(|tile| tile.col == col && tile.row == row && tile.has_image)
+        {
+            tile_info.drawn = Some(drawn);
+        }
+        drawn
+    }
+
+    // Returns [col, row, draw_x, draw_y, draw_w, draw_h] for every loaded tile, with the draw
+    // rectangle in buffer coordinates
+    #[wasm_bindgen]
+    pub fn all_tile_draw_bounds(&self) -> Vec<u32> {
+        self.loaded_tiles
+            .iter()
+            .filter(|tile| tile.has_image)
+            .flat_map(|tile| {
+                let (tile_start_x, tile_start_y) = self.tile_origin(tile.col, tile.row);
+                let (tile_width, tile_height) = self.tile_size(tile.col, tile.row);
+                let [x, y, width, height] = tile.drawn.unwrap_or([0, 0, tile_width, tile_height]);
+                [
+                    tile.col,
+                    tile.row,
+                    tile_start_x as u32 + x,
+                    tile_start_y as u32 + y,
+                    width,
+                    height,
+                ]
+            })
+            .collect()
+    }
+
+    // Helper method to source-over blend packed RGBA pixels onto a tile, placed as in
+    // composite_into_tile. Tile pixels outside the source are left untouched.
+    #[allow(clippy::too_many_arguments)]
+    fn blend_into_tile(
+        &mut self,
+        col: u32,
+        row: u32,
+        src_data: &[u8],
+        src_width: u32,
+        src_height: u32,
+        origin_x: i32,
+        origin_y: i32,
+    ) {
+        self.mark_tile_dirty(col, row);
+        let (tile_start_x, tile_start_y) = self.tile_origin(col, row);
+        let (tile_width, tile_height) = self.tile_size(col, row);
+
+        for y in 0..tile_height as usize {
+            for x in 0..tile_width as usize {
+                let src_x = x as i32 - origin_x;
+                let src_y = y as i32 - origin_y;
+                if src_x < 0 || src_y < 0 || src_x >= src_width as i32 || src_y >= src_height as i32
+                {
+                    continue;
+                }
+
+                let src_index = ((src_y as u32 * src_width + src_x as u32) * 4) as usize;
+                let dst_index = ((tile_start_y + y) * self.width as usize + (tile_start_x + x)) * 4;
+                let blended = self.source_over_fn()(
+                    &src_data[src_index..src_index + 4],
+                    &self.data[dst_index..dst_index + 4],
+                );
+                self.data[dst_index..dst_index + 4].copy_from_slice(&blended);
+            }
+        }
+        self.record_drawn_rect(col, row, src_width, src_height, origin_x, origin_y);
+
+        // Keep any configured frame on top of the new content
+        self.draw_tile_border(col, row);
+    }
+
+    // Helper method to mark, per pixel, whether it lies within any loaded tile. Built once per
+    // pass so background fills stay O(W*H) no matter how many tiles are loaded.
+    fn loaded_pixel_mask(&self) -> Vec<bool> {
+        let mut mask = vec![false; (self.width * self.height) as usize];
+        for tile_info in self.loaded_tiles.iter().filter(|tile| tile.has_image) {
+            for range in self.tile_row_ranges(tile_info.col, tile_info.row) {
+                mask[range.start / 4..range.end / 4].fill(true);
+            }
+        }
+        mask
+    }
+
+    #[wasm_bindgen]
+    pub fn generate_pattern(&mut self, frame: u32) {
+        self.generate_pattern_kind(frame, PatternKind::Waves);
+    }
+
+    #[wasm_bindgen]
+    pub fn generate_pattern_kind(&mut self, frame: u32, kind: PatternKind) {
+        self.generate_pattern_band(frame, kind, 0..self.height);
+    }
+
+    // Generate the default pattern for only `num_rows` pixel rows starting at `start_row`, so a
+    // large buffer can be filled across several animation frames. Rows past the bottom are
+    // ignored; covering every row gives the same result as generate_pattern.
+    #[wasm_bindgen]
+    pub fn generate_pattern_rows(&mut self, frame: u32, start_row: u32, num_rows: u32) {
+        let start = start_row.min(self.height);
+        let end = start_row.saturating_add(num_rows).min(self.height);
+        self.generate_pattern_band(frame, PatternKind::Waves, start..end);
+    }
+
+    // Helper method to draw the pattern over the background pixels of a band of buffer rows
+    fn generate_pattern_band(&mut self, frame: u32, kind: PatternKind, rows: Range<u32>) {
+        let width = self.width as usize;
+        let height = self.height as usize;
+        let loaded = self.loaded_pixel_mask();
+        self.mark_dirty(0, rows.start, self.width, rows.end - rows.start);
+
+        for y in rows.start as usize..rows.end as usize {
+            for x in 0..width {
+                // Skip pixels that are part of any loaded image
+                if loaded[y * width + x] {
+                    continue;
+                }
+
+                let index = (y * width + x) * 4;
+
+                // Create a dynamic pattern based on frame number
+                let color = pattern_color(kind, x, y, width, height, frame);
+                self.data[index..index + 4].copy_from_slice(&color);
+            }
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn fill_background(&mut self) {
+        let width = self.width as usize;
+        let height = self.height as usize;
+        let loaded = self.loaded_pixel_mask();
+        let background = self.stored_background();
+        self.mark_dirty(0, 0, self.width, self.height);
+
+        for y in 0..height {
+            for x in 0..width {
+                // Skip pixels that are part of any loaded image
+                if loaded[y * width + x] {
+                    continue;
+                }
+
+                let index = (y * width + x) * 4;
+
+                // Fill with solid background color
+                self.data[index..index + 4].copy_from_slice(&background);
+            }
+        }
+
+        // Empty tiles with their own background color get it instead of the global one
+        let overrides: Vec<(u32, u32, [u8; 4])> = self
+            .loaded_tiles
+            .iter()
+            .filter(|tile| !tile.has_image)
+            .filter_map(|tile| tile.background.map(|color| (tile.col, tile.row, color)))
+            .collect();
+        for (col, row, color) in overrides {
+            self.fill_tile_region(col, row, color);
+        }
+    }
+
+    // Draw lines of the given thickness centered on every tile edge (both sides of any gutter).
+    // The covered pixels are remembered so clear_grid_lines can put them back.
+    #[wasm_bindgen]
+    pub fn draw_grid_lines(&mut self, thickness: u32, r: u8, g: u8, b: u8, a: u8) {
+        self.clear_grid_lines();
+        if thickness == 0 {
+            return;
+        }
+
+        let mut color = [r, g, b, a];
+        self.match_buffer_alpha(&mut color);
+        let vertical = grid_line_spans(&self.col_offsets, &self.col_widths, thickness, self.width);
+        let horizontal =
+            grid_line_spans(&self.row_offsets, &self.row_heights, thickness, self.height);
+        let width = self.width as usize;
+
+        for y in 0..self.height as usize {
+            let on_row_line = horizontal.iter().any(|span| span.contains(&y));
+            for x in 0..width {
+                if on_row_line || vertical.iter().any(|span| span.contains(&x)) {
+                    let index = (y * width + x) * 4;
+                    let mut original = [0; 4];
+                    original.copy_from_slice(&self.data[index..index + 4]);
+                    self.grid_lines.push((index, original));
+                    self.data[index..index + 4].copy_from_slice(&color);
+                }
+            }
+        }
+        self.grid_line_color = color;
+        self.mark_dirty(0, 0, self.width, self.height);
+    }
+
+    // Remove lines drawn by draw_grid_lines. Pixels repainted since then are left as they are.
+    #[wasm_bindgen]
+    pub fn clear_grid_lines(&mut self) {
+        if self.grid_lines.is_empty() {
+            return;
+        }
+        for (index, original) in std::mem::take(&mut self.grid_lines) {
+            if self.data[index..index + 4] == self.grid_line_color {
+                self.data[index..index + 4].copy_from_slice(&original);
+            }
+        }
+        self.mark_dirty(0, 0, self.width, self.height);
+    }
+
+    // Save the current pixels and tile state for undo. Each snapshot costs a full copy of the
+    // buffer (width * height * 4 bytes); at most MAX_UNDO_SNAPSHOTS are kept, oldest dropped
+    // first. Changing the grid layout starts a fresh history.
+    #[wasm_bindgen]
+    pub fn push_undo_snapshot(&mut self) {
+        if self.undo_stack.len() == MAX_UNDO_SNAPSHOTS {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push(Snapshot {
+            data: self.data.clone(),
+            loaded_tiles: self.loaded_tiles.clone(),
+            premultiplied: self.premultiplied,
+        });
+    }
+
+    // Restore the most recent snapshot. Returns false when there is nothing to undo.
+    #[wasm_bindgen]
+    pub fn undo(&mut self) -> bool {
+        let Some(snapshot) = self.undo_stack.pop() else {
+            return false;
+        };
+        self.data = snapshot.data;
+        self.loaded_tiles = snapshot.loaded_tiles;
+        self.premultiplied = snapshot.premultiplied;
+        self.mark_dirty(0, 0, self.width, self.height);
+        true
+    }
+
+    // Alpha-blend another buffer's pixels over this one with its top-left corner at the offset,
+    // clipping at the edges. Transparent pixels of `other` leave this buffer unchanged.
+    #[wasm_bindgen]
+    pub fn composite_buffer(&mut self, other: &ImageBuffer, offset_x: i32, offset_y: i32) {
+        // Overlapping region in this buffer's coordinates
+        let x0 = offset_x.clamp(0, self.width as i32);
+        let y0 = offset_y.clamp(0, self.height as i32);
+        let x1 = (offset_x + other.width as i32).clamp(x0, self.width as i32);
+        let y1 = (offset_y + other.height as i32).clamp(y0, self.height as i32);
+        let blend = self.source_over_fn();
+
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let src_index = (((y - offset_y) as usize * other.width as usize)
+                    + (x - offset_x) as usize)
+                    * 4;
+                let mut src: [u8; 4] = other.data[src_index..src_index + 4].try_into().unwrap();
+                if src[3] == 0 {
+                    continue;
+                }
+                // The two buffers may store alpha differently
+                match (other.premultiplied, self.premultiplied) {
+                    (true, false) => unpremultiply_pixel(&mut src),
+                    (false, true) => premultiply_pixel(&mut src),
+                    _ => {}
+                }
+                let dst_index = (y as usize * self.width as usize + x as usize) * 4;
+                let blended = blend(&src, &self.data[dst_index..dst_index + 4]);
+                self.data[dst_index..dst_index + 4].copy_from_slice(&blended);
+            }
+        }
+        self.mark_dirty(x0 as u32, y0 as u32, (x1 - x0) as u32, (y1 - y0) as u32);
+    }
+
+    // Paint a checkerboard into fully transparent pixels so transparency is visible in previews.
+    // Opaque and partially transparent pixels are left alone. Colors are [r, g, b] slices.
+    #[wasm_bindgen]
+    pub fn render_transparency_checker(
+        &mut self,
+        square_size: u32,
+        light: &[u8],
+        dark: &[u8],
+    ) -> Result<(), JsValue> {
+        let (Ok(light), Ok(dark)) = (<[u8; 3]>::try_from(light), <[u8; 3]>::try_from(dark)) else {
+            return Err(JsValue::from_str(&format!(
+                "Checker colors must have 3 components, got {} and {}",
+                light.len(),
+                dark.len()
+            )));
+        };
+
+        let width = self.width as usize;
+        let square_size = square_size.max(1) as usize;
+        self.mark_dirty(0, 0, self.width, self.height);
+
+        for (i, pixel) in self.data.chunks_exact_mut(4).enumerate() {
+            if pixel[3] != 0 {
+                continue;
+            }
+            let (x, y) = (i % width, i / width);
+            let color = if (x / square_size + y / square_size) % 2 == 1 {
+                dark
+            } else {
+                light
+            };
+            pixel.copy_from_slice(&[color[0], color[1], color[2], 255]);
+        }
+
+        Ok(())
+    }
+
+    // Serialize the grid configuration and per-tile state (not pixels) for saving a project
+    #[wasm_bindgen]
+    pub fn export_layout_json(&self) -> String {
+        let uniform = self.col_widths.iter().all(|&w| w == self.tile_width)
+            && self.row_heights.iter().all(|&h| h == self.tile_height);
+        let layout = LayoutJson {
+            tile_width: self.tile_width,
+            tile_height: self.tile_height,
+            num_cols: self.num_cols,
+            num_rows: self.num_rows,
+            gutter: self.gutter,
+            col_widths: (!uniform).then(|| self.col_widths.clone()),
+            row_heights: (!uniform).then(|| self.row_heights.clone()),
+            background: [
+                self.background_r,
+                self.background_g,
+                self.background_b,
+                self.background_a,
+            ],
+            letterbox: self.letterbox,
+            tiles: self
+                .loaded_tiles
+                .iter()
+                .map(|tile| TileLayoutJson {
+                    col: tile.col,
+                    row: tile.row,
+                    has_image: tile.has_image,
+                    scale: tile.scale,
+                    offset_x: tile.offset_x,
+                    offset_y: tile.offset_y,
+                    opacity: tile.opacity,
+                    background: tile.background,
+                    border: tile.border,
+                    locked: tile.locked,
+                })
+                .collect(),
+        };
+        // Plain structs of numbers always serialize
+        serde_json::to_string(&layout).unwrap()
+    }
+
+    // Rebuild the grid and per-tile settings from export_layout_json output. Images are not
+    // stored, so tiles listed with has_image need to be re-loaded by the caller.
+    #[wasm_bindgen]
+    pub fn import_layout_json(&mut self, json: &str) -> Result<(), JsValue> {
+        let layout: LayoutJson = serde_json::from_str(json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid layout JSON: {}", e)))?;
+
+        let col_widths = layout
+            .col_widths
+            .unwrap_or_else(|| vec![layout.tile_width; layout.num_cols as usize]);
+        let row_heights = layout
+            .row_heights
+            .unwrap_or_else(|| vec![layout.tile_height; layout.num_rows as usize]);
+        if col_widths.len() != layout.num_cols as usize
+            || row_heights.len() != layout.num_rows as usize
+        {
+            return Err(JsValue::from_str(&format!(
+                "Layout sizes do not match a {}x{} grid",
+                layout.num_cols, layout.num_rows
+            )));
+        }
+
+        let mut imported = ImageBuffer::from_layout(col_widths, row_heights, layout.gutter)?;
+        for tile in &layout.tiles {
+            imported.validate_tile_position(tile.col, tile.row)?;
+        }
+        let [r, g, b, a] = layout.background;
+        imported.set_background_color(r, g, b, a);
+        imported.letterbox = layout.letterbox;
+        imported.loaded_tiles = layout
+            .tiles
+            .iter()
+            .map(|tile| TileInfo {
+                background: tile.background,
+                border: tile.border,
+                locked: tile.locked,
+                ..TileInfo::empty(tile.col, tile.row)
+            })
+            .filter(|tile| tile.has_settings())
+            .collect();
+        imported.fill_background();
+
+        let proxy_factor = self.proxy_factor;
+        *self = imported;
+        self.enable_proxy(proxy_factor);
+
+        Ok(())
+    }
+
+    // Load many images from one concatenated blob in a single call. `manifest` holds groups of
+    // [col, row, byte_offset, byte_len] locating each image in `blob`. Entries that fail are
+    // skipped and reported in the returned JSON array of {index, col, row, error} objects.
+    #[wasm_bindgen]
+    pub fn load_batch(&mut self, blob: &[u8], manifest: &[u32]) -> Result<String, JsValue> {
+        if !manifest.len().is_multiple_of(4) {
+            return Err(JsValue::from_str(&format!(
+                "Manifest length {} is not a multiple of 4",
+                manifest.len()
+            )));
+        }
+
+        let mut errors = Vec::new();
+        for (index, entry) in manifest.chunks_exact(4).enumerate() {
+            let [col, row, offset, len] = [entry[0], entry[1], entry[2], entry[3]];
+            let result = match (offset as usize)
+                .checked_add(len as usize)
+                .and_then(|end| blob.get(offset as usize..end))
+            {
+                Some(image_data) => self
+                    .load_image_from_bytes(image_data, col, row)
+                    .map_err(|e| e.as_string().unwrap_or_default()),
+                None => Err(format!(
+                    "Byte range {}+{} is outside the {}-byte blob",
+                    offset,
+                    len,
+                    blob.len()
+                )),
+            };
+            if let Err(error) = result {
+                errors.push(BatchError {
+                    index,
+                    col,
+                    row,
+                    error,
+                });
+            }
+        }
+
+        Ok(serde_json::to_string(&errors).unwrap())
+    }
+
+    #[wasm_bindgen]
+    pub fn load_image_from_bytes(
+        &mut self,
+        image_data: &[u8],
+        col: u32,
+        row: u32,
+    ) -> Result<(), JsValue> {
+        self.load_image_from_bytes_with_scale(image_data, col, row, 1.0)
+    }
+
+    #[wasm_bindgen]
+    pub fn load_image_from_bytes_with_scale(
+        &mut self,
+        image_data: &[u8],
+        col: u32,
+        row: u32,
+        scale: f32,
+    ) -> Result<(), JsValue> {
+        self.load_bytes_with_filter(image_data, col, row, scale, None)
+    }
+
+    // Like load_image_from_bytes_with_scale, but returns the milliseconds spent decoding,
+    // resizing and compositing, for profiling. The untimed loads skip the clock reads.
+    #[wasm_bindgen]
+    pub fn load_image_from_bytes_timed(
+        &mut self,
+        image_data: &[u8],
+        col: u32,
+        row: u32,
+        scale: f32,
+    ) -> Result<f64, JsValue> {
+        let start = now_ms();
+        self.load_image_from_bytes_with_scale(image_data, col, row, scale)?;
+        Ok(now_ms() - start)
+    }
+
+    // Like load_image_from_bytes_with_scale, but always resizes with the given filter instead of
+    // picking one from the enlargement
+    #[wasm_bindgen]
+    pub fn load_image_from_bytes_with_filter(
+        &mut self,
+        image_data: &[u8],
+        col: u32,
+        row: u32,
+        scale: f32,
+        filter: ResizeFilter,
+    ) -> Result<(), JsValue> {
+        self.load_bytes_with_filter(image_data, col, row, scale, Some(filter))
+    }
+
+    // Helper method to decode and load image bytes at a scale, with an explicit filter or,
+    // given None, one chosen by auto_filter
+    fn load_bytes_with_filter(
+        &mut self,
+        image_data: &[u8],
+        col: u32,
+        row: u32,
+        scale: f32,
+        filter: Option<ResizeFilter>,
+    ) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        ImageBuffer::validate_scale(scale)?;
+        let (tile_width, tile_height) = self.tile_size(col, row);
+        let img = decode_image_to_fit(
+            image_data,
+            (tile_width as f32 * scale) as u32,
+            (tile_height as f32 * scale) as u32,
+        )?;
+
+        self.load_decoded_with_scale(img, col, row, scale, filter, false);
+
+        Ok(())
+    }
+
+    // Load an image after cropping away a uniform border around it, such as the white margins
+    // of a scan, so the content fills the tile. The border color is taken from the top-left
+    // pixel; edge rows and columns whose every channel is within `bg_tolerance` of it are
+    // trimmed. Images without such a border, or that are uniform throughout, load unchanged.
+    #[wasm_bindgen]
+    pub fn load_image_autotrim(
+        &mut self,
+        image_data: &[u8],
+        col: u32,
+        row: u32,
+        scale: f32,
+        bg_tolerance: u8,
+    ) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        ImageBuffer::validate_scale(scale)?;
+
+        let mut img = decode_image(image_data)?;
+        if let Some((x, y, width, height)) = uniform_border_crop(&img, bg_tolerance) {
+            img = img.crop_imm(x, y, width, height);
+        }
+
+        self.load_decoded_with_scale(img, col, row, scale, None, false);
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn load_image_from_rgba(
+        &mut self,
+        pixels: &[u8],
+        src_width: u32,
+        src_height: u32,
+        col: u32,
+        row: u32,
+        scale: f32,
+    ) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        ImageBuffer::validate_scale(scale)?;
+
+        if src_width == 0 || src_height == 0 {
+            return Err(JsValue::from_str(&format!(
+                "Invalid RGBA image size {}x{}",
+                src_width, src_height
+            )));
+        }
+
+        // Validate RGBA data length
+        let expected_len = (src_width as usize)
+            .checked_mul(src_height as usize)
+            .and_then(|len| len.checked_mul(4));
+        if expected_len != Some(pixels.len()) {
+            return Err(JsValue::from_str(&format!(
+                "Invalid RGBA data length for {}x{} image. Expected {}, got {}",
+                src_width,
+                src_height,
+                expected_len.map_or("overflow".to_string(), |len| len.to_string()),
+                pixels.len()
+            )));
+        }
+        let img = image::RgbaImage::from_raw(src_width, src_height, pixels.to_vec())
+            .ok_or_else(|| JsValue::from_str("Failed to wrap RGBA pixels"))?;
+
+        self.load_decoded_with_scale(DynamicImage::ImageRgba8(img), col, row, scale, None, false);
+
+        Ok(())
+    }
+
+    // Helper method to resize an already decoded image for the given scale and draw it into
+    // the tile, cropping the center when it outgrows the tile. A filter of None lets
+    // auto_filter pick one for the enlargement.
+    fn load_decoded_with_scale(
+        &mut self,
+        img: DynamicImage,
+        col: u32,
+        row: u32,
+        scale: f32,
+        filter: Option<ResizeFilter>,
+        blend: bool,
+    ) {
+        let (tile_width, tile_height) = self.tile_size(col, row);
+
+        // Calculate scaled dimensions
+        let scaled_width = (tile_width as f32 * scale) as u32;
+        let scaled_height = (tile_height as f32 * scale) as u32;
+
+        let filter = filter.unwrap_or_else(|| fit_filter(&img, scaled_width, scaled_height));
+        let resized_img = resize_preserve_aspect_ratio(&img, scaled_width, scaled_height, filter);
+        let mut rgba_img = to_rgba8_dithered(&resized_img);
+        self.match_buffer_alpha(&mut rgba_img);
+
+        // Get actual dimensions after aspect ratio preserving resize
+        let actual_width = rgba_img.width();
+        let actual_height = rgba_img.height();
+
+        // Remove any existing tile info for this position, then add new one
+        let tile_info = self.mark_tile_loaded(col, row);
+        tile_info.source = Some(Rc::new(img));
+        tile_info.scale = scale;
+
+        // Calculate offsets for centering/cropping
+        let (src_offset_x, src_offset_y, dst_offset_x, dst_offset_y) = if scale >= 1.0 {
+            // Scale >= 100%: crop center of scaled image to fit tile
+            let crop_x = (actual_width.saturating_sub(tile_width)) / 2;
+            let crop_y = (actual_height.saturating_sub(tile_height)) / 2;
+            (crop_x, crop_y, 0, 0)
+        } else {
+            // Scale < 100%: center smaller image within tile
+            let center_x = (tile_width - actual_width) / 2;
+            let center_y = (tile_height - actual_height) / 2;
+            (0, 0, center_x, center_y)
+        };
+
+        let origin_x = dst_offset_x as i32 - src_offset_x as i32;
+        let origin_y = dst_offset_y as i32 - src_offset_y as i32;
+        if blend {
+            // Blend the image over whatever the tile currently shows
+            self.blend_into_tile(
+                col,
+                row,
+                rgba_img.as_raw(),
+                actual_width,
+                actual_height,
+                origin_x,
+                origin_y,
+            );
+        } else {
+            // Draw the image into the tile, filling uncovered areas with background
+            self.composite_into_tile(
+                col,
+                row,
+                rgba_img.as_raw(),
+                actual_width,
+                actual_height,
+                origin_x,
+                origin_y,
+            );
+        }
+    }
+
+    // Fit one image (times scale) to the block of span_cols x span_rows cells starting at
+    // (start_col, start_row), treating the block as a single tile: smaller images are centered
+    // and larger ones are center-cropped. Each covered cell is marked loaded and shows its part
+    // of the image; gutters inside the block stay background. The cells don't keep the source,
+    // since re-compositing a cell on its own could not reproduce the span.
+    #[wasm_bindgen]
+    pub fn load_image_spanning(
+        &mut self,
+        image_data: &[u8],
+        start_col: u32,
+        start_row: u32,
+        span_cols: u32,
+        span_rows: u32,
+        scale: f32,
+    ) -> Result<(), JsValue> {
+        // Validate tile position and span
+        self.validate_tile_unlocked(start_col, start_row)?;
+        let fits = span_cols > 0
+            && span_rows > 0
+            && start_col
+                .checked_add(span_cols)
+                .is_some_and(|end| end <= self.num_cols)
+            && start_row
+                .checked_add(span_rows)
+                .is_some_and(|end| end <= self.num_rows);
+        if !fits {
+            return Err(JsValue::from_str(&format!(
+                "Span of {}x{} cells from ({}, {}) doesn't fit the {}x{} grid",
+                span_cols, span_rows, start_col, start_row, self.num_cols, self.num_rows
+            )));
+        }
+        for row in start_row..start_row + span_rows {
+            for col in start_col..start_col + span_cols {
+                self.validate_tile_unlocked(col, row)?;
+            }
+        }
+        ImageBuffer::validate_scale(scale)?;
+        let img = decode_image(image_data)?;
+
+        // The block's buffer rectangle, including any gutters between its cells
+        let end_col = start_col + span_cols - 1;
+        let end_row = start_row + span_rows - 1;
+        let (block_x, block_y) = self.tile_origin(start_col, start_row);
+        let (end_x, end_y) = self.tile_origin(end_col, end_row);
+        let (end_width, end_height) = self.tile_size(end_col, end_row);
+        let block_width = (end_x - block_x) as u32 + end_width;
+        let block_height = (end_y - block_y) as u32 + end_height;
+
+        let (target_width, target_height) = (
+            (block_width as f32 * scale) as u32,
+            (block_height as f32 * scale) as u32,
+        );
+        let resized_img = resize_preserve_aspect_ratio(
+            &img,
+            target_width,
+            target_height,
+            fit_filter(&img, target_width, target_height),
+        );
+        let mut rgba_img = to_rgba8_dithered(&resized_img);
+        self.match_buffer_alpha(&mut rgba_img);
+        let (actual_width, actual_height) = rgba_img.dimensions();
+        let origin_x = (block_width as i32 - actual_width as i32) / 2;
+        let origin_y = (block_height as i32 - actual_height as i32) / 2;
+
+        for row in start_row..=end_row {
+            for col in start_col..=end_col {
+                let (tile_x, tile_y) = self.tile_origin(col, row);
+                self.mark_tile_loaded(col, row).scale = scale;
+                self.composite_into_tile(
+                    col,
+                    row,
+                    rgba_img.as_raw(),
+                    actual_width,
+                    actual_height,
+                    origin_x - (tile_x - block_x) as i32,
+                    origin_y - (tile_y - block_y) as i32,
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    // Load a specific GIF frame (0-based) instead of the first; indices past the last frame
+    // clamp to it
+    #[wasm_bindgen]
+    pub fn load_gif_frame(
+        &mut self,
+        image_data: &[u8],
+        col: u32,
+        row: u32,
+        frame_index: u32,
+        scale: f32,
+    ) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        ImageBuffer::validate_scale(scale)?;
+        let img = decode_gif_frame(image_data, frame_index)?;
+
+        self.load_decoded_with_scale(img, col, row, scale, None, false);
+
+        Ok(())
+    }
+
+    // Like load_image_from_bytes, but caps the decoded image at max_decode_dim on its longest side
+    // before the tile resize, keeping peak memory low for huge uploads
+    #[wasm_bindgen]
+    pub fn load_image_downscaled(
+        &mut self,
+        image_data: &[u8],
+        col: u32,
+        row: u32,
+        max_decode_dim: u32,
+    ) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        let img = decode_image_downscaled(image_data, max_decode_dim)?;
+
+        self.load_decoded_with_scale(img, col, row, 1.0, None, false);
+
+        Ok(())
+    }
+
+    // Fit the image (times scale) inside the tile minus `padding` on every side and center it.
+    // The padding is plain tile background and clips anything that would spill into it.
+    #[wasm_bindgen]
+    pub fn load_image_with_padding(
+        &mut self,
+        image_data: &[u8],
+        col: u32,
+        row: u32,
+        scale: f32,
+        padding: u32,
+    ) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        ImageBuffer::validate_scale(scale)?;
+        let (tile_width, tile_height) = self.tile_size(col, row);
+        let inner_width = tile_width.saturating_sub(padding.saturating_mul(2));
+        let inner_height = tile_height.saturating_sub(padding.saturating_mul(2));
+        if inner_width == 0 || inner_height == 0 {
+            return Err(JsValue::from_str(&format!(
+                "Padding {} must be less than half of the {}x{} tile",
+                padding, tile_width, tile_height
+            )));
+        }
+        let img = decode_image(image_data)?;
+
+        let (target_width, target_height) = (
+            (inner_width as f32 * scale) as u32,
+            (inner_height as f32 * scale) as u32,
+        );
+        let resized_img = resize_preserve_aspect_ratio(
+            &img,
+            target_width,
+            target_height,
+            fit_filter(&img, target_width, target_height),
+        );
+        let mut rgba_img = to_rgba8_dithered(&resized_img);
+        self.match_buffer_alpha(&mut rgba_img);
+        let (actual_width, actual_height) = rgba_img.dimensions();
+
+        let tile_info = self.mark_tile_loaded(col, row);
+        tile_info.source = Some(Rc::new(img));
+        tile_info.scale = scale;
+
+        self.composite_into_tile(
+            col,
+            row,
+            rgba_img.as_raw(),
+            actual_width,
+            actual_height,
+            (tile_width as i32 - actual_width as i32) / 2,
+            (tile_height as i32 - actual_height as i32) / 2,
+        );
+
+        // Repaint the padding band with background, clipping any overflow
+        let background = self.stored_tile_background(col, row);
+        let padding = padding as usize;
+        let (tile_width, tile_height) = (tile_width as usize, tile_height as usize);
+        let mut i = 0;
+        self.for_each_tile_pixel(col, row, |pixel| {
+            let (x, y) = (i % tile_width, i / tile_width);
+            i += 1;
+            if x < padding || y < padding || x >= tile_width - padding || y >= tile_height - padding
+            {
+                pixel.copy_from_slice(&background);
+            }
+        });
+        self.draw_tile_border(col, row);
+
+        Ok(())
+    }
+
+    // Upscale the image by a whole-number factor with nearest-neighbor sampling and center it,
+    // cropping whatever exceeds the tile, so pixel art keeps crisp square pixels. A factor of 0
+    // picks the largest one at which the whole image still fits (at least 1).
+    #[wasm_bindgen]
+    pub fn load_pixel_art(
+        &mut self,
+        image_data: &[u8],
+        col: u32,
+        row: u32,
+        integer_scale: u32,
+    ) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        let (tile_width, tile_height) = self.tile_size(col, row);
+        let img = decode_image(image_data)?;
+        let mut rgba_img = img.to_rgba8();
+        self.match_buffer_alpha(&mut rgba_img);
+        let (src_width, src_height) = rgba_img.dimensions();
+
+        let factor = if integer_scale == 0 {
+            (tile_width / src_width)
+                .min(tile_height / src_height)
+                .max(1)
+        } else {
+            integer_scale
+        } as i64;
+
+        // Only sample the part of the upscaled image that lands inside the tile
+        let origin_x = (tile_width as i64 - src_width as i64 * factor) / 2;
+        let origin_y = (tile_height as i64 - src_height as i64 * factor) / 2;
+        let visible_x =
+            origin_x.max(0)..(origin_x + src_width as i64 * factor).min(tile_width as i64);
+        let visible_y =
+            origin_y.max(0)..(origin_y + src_height as i64 * factor).min(tile_height as i64);
+
+        let mut pixels =
+            Vec::with_capacity(visible_x.clone().count() * visible_y.clone().count() * 4);
+        for y in visible_y.clone() {
+            let src_y = ((y - origin_y) / factor) as u32;
+            for x in visible_x.clone() {
+                let src_x = ((x - origin_x) / factor) as u32;
+                pixels.extend_from_slice(&rgba_img.get_pixel(src_x, src_y).0);
+            }
+        }
+
+        let tile_info = self.mark_tile_loaded(col, row);
+        tile_info.source = Some(Rc::new(img));
+
+        self.composite_into_tile(
+            col,
+            row,
+            &pixels,
+            visible_x.count() as u32,
+            visible_y.count() as u32,
+            origin_x.max(0) as i32,
+            origin_y.max(0) as i32,
+        );
+
+        Ok(())
+    }
+
+    // Place the scaled image against the given anchor instead of centering it. Images smaller
+    // than the tile are padded on the opposite side; larger ones keep the anchored part.
+    #[wasm_bindgen]
+    pub fn load_image_with_anchor(
+        &mut self,
+        image_data: &[u8],
+        col: u32,
+        row: u32,
+        scale: f32,
+        anchor: Anchor,
+    ) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        ImageBuffer::validate_scale(scale)?;
+        let (tile_width, tile_height) = self.tile_size(col, row);
+        let img = decode_image(image_data)?;
+
+        let (target_width, target_height) = (
+            (tile_width as f32 * scale) as u32,
+            (tile_height as f32 * scale) as u32,
+        );
+        let resized_img = resize_preserve_aspect_ratio(
+            &img,
+            target_width,
+            target_height,
+            fit_filter(&img, target_width, target_height),
+        );
+        let mut rgba_img = to_rgba8_dithered(&resized_img);
+        self.match_buffer_alpha(&mut rgba_img);
+        let (actual_width, actual_height) = rgba_img.dimensions();
+
+        let tile_info = self.mark_tile_loaded(col, row);
+        tile_info.source = Some(Rc::new(img));
+        tile_info.scale = scale;
+
+        // Split the leftover (or overflowing) space according to the anchor
+        let (halves_x, halves_y) = anchor.halves();
+        let origin_x = (tile_width as i32 - actual_width as i32) * halves_x / 2;
+        let origin_y = (tile_height as i32 - actual_height as i32) * halves_y / 2;
+
+        self.composite_into_tile(
+            col,
+            row,
+            rgba_img.as_raw(),
+            actual_width,
+            actual_height,
+            origin_x,
+            origin_y,
+        );
+
+        Ok(())
+    }
+
+    // Like load_image_from_bytes_with_scale, but source-over blends the image onto the tile's
+    // current pixels instead of replacing them
+    #[wasm_bindgen]
+    pub fn load_image_from_bytes_blend(
+        &mut self,
+        image_data: &[u8],
+        col: u32,
+        row: u32,
+        scale: f32,
+    ) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        ImageBuffer::validate_scale(scale)?;
+        let img = decode_image(image_data)?;
+
+        self.load_decoded_with_scale(img, col, row, scale, None, true);
+
+        Ok(())
+    }
+
+    // Start packing several images into one tile on a sub_cols x sub_rows sub-grid. The tile is
+    // cleared to its background; add_collage_image then fills sub-cells in row-major order.
+    #[wasm_bindgen]
+    pub fn begin_tile_collage(
+        &mut self,
+        col: u32,
+        row: u32,
+        sub_cols: u32,
+        sub_rows: u32,
+    ) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        if sub_cols == 0 || sub_rows == 0 {
+            return Err(JsValue::from_str(&format!(
+                "Invalid collage grid {}x{}",
+                sub_cols, sub_rows
+            )));
+        }
+
+        self.mark_tile_loaded(col, row);
+        let background = self.tile_background(col, row);
+        self.fill_tile_region(col, row, background);
+        self.draw_tile_border(col, row);
+
+        self.collage = Some(Collage {
+            col,
+            row,
+            sub_cols,
+            sub_rows,
+            next: 0,
+        });
+
+        Ok(())
+    }
+
+    // Decode an image and fit it, centered, into the next free sub-cell of the current collage
+    #[wasm_bindgen]
+    pub fn add_collage_image(&mut self, image_data: &[u8]) -> Result<(), JsValue> {
+        let Some(collage) = &self.collage else {
+            return Err(JsValue::from_str(
+                "No collage in progress; call begin_tile_collage first",
+            ));
+        };
+        let (col, row, sub_cols, sub_rows, index) = (
+            collage.col,
+            collage.row,
+            collage.sub_cols,
+            collage.sub_rows,
+            collage.next,
+        );
+        if index >= sub_cols * sub_rows {
+            return Err(JsValue::from_str(&format!(
+                "Collage in tile ({}, {}) is full",
+                col, row
+            )));
+        }
+        // The tile may have been locked since begin_tile_collage
+        self.validate_tile_unlocked(col, row)?;
+        let img = decode_image(image_data)?;
+
+        // Sub-cell bounds relative to the tile; rounding leftovers spread across the cells
+        let (tile_width, tile_height) = self.tile_size(col, row);
+        let (sub_col, sub_row) = (index % sub_cols, index / sub_cols);
+        let cell_x0 = sub_col * tile_width / sub_cols;
+        let cell_x1 = (sub_col + 1) * tile_width / sub_cols;
+        let cell_y0 = sub_row * tile_height / sub_rows;
+        let cell_y1 = (sub_row + 1) * tile_height / sub_rows;
+        let (cell_width, cell_height) = (cell_x1 - cell_x0, cell_y1 - cell_y0);
+        if let Some(collage) = &mut self.collage {
+            collage.next += 1;
+        }
+        if cell_width == 0 || cell_height == 0 {
+            // More sub-cells than pixels; this one has no room to draw in
+            return Ok(());
+        }
+
+        let resized_img = resize_preserve_aspect_ratio(
+            &img,
+            cell_width,
+            cell_height,
+            fit_filter(&img, cell_width, cell_height),
+        );
+        let mut rgba_img = to_rgba8_dithered(&resized_img);
+        self.match_buffer_alpha(&mut rgba_img);
+        let draw_x = cell_x0 + (cell_width - rgba_img.width()) / 2;
+        let draw_y = cell_y0 + (cell_height - rgba_img.height()) / 2;
+
+        let (tile_start_x, tile_start_y) = self.tile_origin(col, row);
+        for (x, y, pixel) in rgba_img.enumerate_pixels() {
+            let dst_x = tile_start_x + (draw_x + x) as usize;
+            let dst_y = tile_start_y + (draw_y + y) as usize;
+            let index = (dst_y * self.width as usize + dst_x) * 4;
+            self.data[index..index + 4].copy_from_slice(&pixel.0);
+        }
+        self.mark_tile_dirty(col, row);
+        self.draw_tile_border(col, row);
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn load_image_from_bytes_with_mode(
+        &mut self,
+        image_data: &[u8],
+        col: u32,
+        row: u32,
+        mode: ScaleMode,
+    ) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        let (tile_width, tile_height) = self.tile_size(col, row);
+        let img = decode_image(image_data)?;
+
+        let resized_img = match mode {
+            ScaleMode::Fit => resize_preserve_aspect_ratio(
+                &img,
+                tile_width,
+                tile_height,
+                fit_filter(&img, tile_width, tile_height),
+            ),
+            ScaleMode::Fill => resize_to_cover(
+                &img,
+                tile_width,
+                tile_height,
+                cover_filter(&img, tile_width, tile_height),
+            ),
+            ScaleMode::Stretch => img.resize_exact(
+                tile_width,
+                tile_height,
+                cover_filter(&img, tile_width, tile_height).filter_type(),
+            ),
+            ScaleMode::Repeat => repeat_to_fill(&img, tile_width, tile_height),
+            ScaleMode::FitWidth => resize_to_width(
+                &img,
+                tile_width,
+                auto_filter(tile_width as f32 / img.width().max(1) as f32),
+            ),
+            ScaleMode::FitHeight => resize_to_height(
+                &img,
+                tile_height,
+                auto_filter(tile_height as f32 / img.height().max(1) as f32),
+            ),
+            ScaleMode::DownscaleOnly
+                if img.width() <= tile_width && img.height() <= tile_height =>
+            {
+                img.clone()
+            }
+            // Only reached when shrinking, so the fit filter is always Lanczos3 here
+            ScaleMode::DownscaleOnly => resize_preserve_aspect_ratio(
+                &img,
+                tile_width,
+                tile_height,
+                fit_filter(&img, tile_width, tile_height),
+            ),
+        };
+        let mut rgba_img = to_rgba8_dithered(&resized_img);
+        self.match_buffer_alpha(&mut rgba_img);
+        let (actual_width, actual_height) = rgba_img.dimensions();
+
+        self.mark_tile_loaded(col, row).source = Some(Rc::new(img));
+
+        // Center the image: smaller dimensions get padded, larger ones get cropped evenly
+        let origin_x = (tile_width as i32 - actual_width as i32) / 2;
+        let origin_y = (tile_height as i32 - actual_height as i32) / 2;
+
+        self.composite_into_tile(
+            col,
+            row,
+            rgba_img.as_raw(),
+            actual_width,
+            actual_height,
+            origin_x,
+            origin_y,
+        );
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn load_image_from_bytes_with_scale_and_offset(
+        &mut self,
+        image_data: &[u8],
+        col: u32,
+        row: u32,
+        scale: f32,
+        offset_x: i32,
+        offset_y: i32,
+    ) -> Result<Vec<u32>, JsValue> {
+        self.load_image_from_bytes_with_offset_outside(
+            image_data,
+            col,
+            row,
+            scale,
+            offset_x,
+            offset_y,
+            OutsideMode::Background,
+        )
+    }
+
+    // Like load_image_from_bytes_with_scale_and_offset, choosing how the part of the tile the
+    // offset image leaves uncovered is filled. The mode sticks to the tile, so later
+    // rescale_tile/reoffset_tile pans keep extending the edges (or transparency) the same way.
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn load_image_from_bytes_with_offset_outside(
+        &mut self,
+        image_data: &[u8],
+        col: u32,
+        row: u32,
+        scale: f32,
+        offset_x: i32,
+        offset_y: i32,
+        outside: OutsideMode,
+    ) -> Result<Vec<u32>, JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        ImageBuffer::validate_scale(scale)?;
+
+        let img = decode_image(image_data)?;
+
+        // Remove any existing tile info for this position, then add new one
+        let tile_info = self.mark_tile_loaded(col, row);
+        tile_info.scale = scale;
+        tile_info.offset_x = offset_x;
+        tile_info.offset_y = offset_y;
+        tile_info.outside = outside;
+
+        let drawn = self.composite_with_scale_and_offset(&img, col, row, scale, offset_x, offset_y);
+        self.tile_info_mut(col, row).source = Some(Rc::new(img));
+
+        // [draw_x, draw_y, draw_w, draw_h] of the image pixels in buffer coordinates
+        Ok(drawn.to_vec())
+    }
+
+    // Like load_image_from_bytes_with_scale_and_offset, but the offset may be fractional. With
+    // `bilinear` set, the resized image is resampled at the fractional source positions so panning
+    // by sub-pixel amounts moves the picture smoothly; without it the offset is rounded to whole
+    // pixels and copied point-sampled as before. The tile keeps the rounded offset for later
+    // rescale_tile/reoffset_tile redraws.
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn load_image_from_bytes_with_subpixel_offset(
+        &mut self,
+        image_data: &[u8],
+        col: u32,
+        row: u32,
+        scale: f32,
+        offset_x: f32,
+        offset_y: f32,
+        bilinear: bool,
+    ) -> Result<Vec<u32>, JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        ImageBuffer::validate_scale(scale)?;
+        if !offset_x.is_finite() || !offset_y.is_finite() {
+            return Err(JsValue::from_str(&format!(
+                "Offsets must be finite, got ({}, {})",
+                offset_x, offset_y
+            )));
+        }
+        let (offset_x, offset_y) = if bilinear {
+            (offset_x, offset_y)
+        } else {
+            (offset_x.round(), offset_y.round())
+        };
+
+        let img = decode_image(image_data)?;
+
+        let tile_info = self.mark_tile_loaded(col, row);
+        tile_info.scale = scale;
+        tile_info.offset_x = offset_x.round() as i32;
+        tile_info.offset_y = offset_y.round() as i32;
+
+        let drawn = self
+            .composite_with_scale_and_subpixel_offset(&img, col, row, scale, offset_x, offset_y);
+        self.tile_info_mut(col, row).source = Some(Rc::new(img));
+
+        // [draw_x, draw_y, draw_w, draw_h] of the image pixels in buffer coordinates
+        Ok(drawn.to_vec())
+    }
+
+    // Helper method to resize a source image for the given scale and draw it into the tile,
+    // shifted by the user offset. Returns the buffer rectangle covered by image pixels.
+    fn composite_with_scale_and_offset(
+        &mut self,
+        img: &DynamicImage,
+        col: u32,
+        row: u32,
+        scale: f32,
+        offset_x: i32,
+        offset_y: i32,
+    ) -> [u32; 4] {
+        self.composite_with_scale_and_subpixel_offset(
+            img,
+            col,
+            row,
+            scale,
+            offset_x as f32,
+            offset_y as f32,
+        )
+    }
+
+    // The whole-pixel part of each offset positions the image exactly as integer offsets do;
+    // any fractional remainder is applied by bilinearly resampling the resized image.
+    fn composite_with_scale_and_subpixel_offset(
+        &mut self,
+        img: &DynamicImage,
+        col: u32,
+        row: u32,
+        scale: f32,
+        offset_x: f32,
+        offset_y: f32,
+    ) -> [u32; 4] {
+        let (tile_width, tile_height) = self.tile_size(col, row);
+
+        // Calculate scaled dimensions
+        let scaled_width = (tile_width as f32 * scale) as u32;
+        let scaled_height = (tile_height as f32 * scale) as u32;
+
+        let resized_img = resize_preserve_aspect_ratio(
+            img,
+            scaled_width,
+            scaled_height,
+            fit_filter(img, scaled_width, scaled_height),
+        );
+        let mut rgba_img = to_rgba8_dithered(&resized_img);
+        self.match_buffer_alpha(&mut rgba_img);
+
+        let (whole_x, whole_y) = (offset_x.floor(), offset_y.floor());
+        let (fraction_x, fraction_y) = (offset_x - whole_x, offset_y - whole_y);
+        if fraction_x > 0.0 || fraction_y > 0.0 {
+            rgba_img = shift_subpixel(&rgba_img, fraction_x, fraction_y);
+        }
+        let (offset_x, offset_y) = (whole_x as i32, whole_y as i32);
+
+        // Get actual dimensions after aspect ratio preserving resize
+        let actual_width = rgba_img.width();
+        let actual_height = rgba_img.height();
+
+        // Calculate positioning with user offset - use a unified approach for all scales
+        // Always position the image within the tile space, allowing offsets to move it around
+        let base_dst_x = if actual_width <= tile_width {
+            // Image is smaller or equal to tile - center it
+            (tile_width - actual_width) / 2
+        } else {
+            // Image is larger - no base destination offset, will crop from source
+            0
+        };
+
+        let base_dst_y = if actual_height <= tile_height {
+            // Image is smaller or equal to tile - center it
+            (tile_height - actual_height) / 2
+        } else {
+            // Image is larger - no base destination offset, will crop from source
+            0
+        };
+
+        // Apply user offset to destination positioning
+        let dst_offset_x = (base_dst_x as i32 + offset_x)
+            .max(-(actual_width as i32))
+            .min(tile_width as i32) as u32;
+        let dst_offset_y = (base_dst_y as i32 + offset_y)
+            .max(-(actual_height as i32))
+            .min(tile_height as i32) as u32;
+
+        // Calculate source cropping if image extends beyond tile bounds
+        let src_offset_x = if actual_width > tile_width {
+            // Image is larger than tile - crop from center, adjusted by offset effect
+            let base_crop = (actual_width - tile_width) / 2;
+            (base_crop as i32 - offset_x)
+                .max(0)
+                .min((actual_width.saturating_sub(tile_width)) as i32) as u32
+        } else {
+            0
+        };
+
+        let src_offset_y = if actual_height > tile_height {
+            // Image is larger than tile - crop from center, adjusted by offset effect
+            let base_crop = (actual_height - tile_height) / 2;
+            (base_crop as i32 - offset_y)
+                .max(0)
+                .min((actual_height.saturating_sub(tile_height)) as i32) as u32
+        } else {
+            0
+        };
+
+        // Draw the image into the tile, filling uncovered areas as the tile's outside mode says
+        let outside = self
+            .tile_info(col, row)
+            .map_or(OutsideMode::Background, |tile| tile.outside);
+        self.composite_into_tile_with_outside(
+            col,
+            row,
+            rgba_img.as_raw(),
+            actual_width,
+            actual_height,
+            dst_offset_x as i32 - src_offset_x as i32,
+            dst_offset_y as i32 - src_offset_y as i32,
+            outside,
+        )
+    }
+
+    #[wasm_bindgen]
+    pub fn rescale_tile(&mut self, col: u32, row: u32, scale: f32) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        ImageBuffer::validate_scale(scale)?;
+
+        let (source, offset_x, offset_y) = {
+            let tile_info = self.retained_tile(col, row)?;
+            (
+                tile_info.source.clone(),
+                tile_info.offset_x,
+                tile_info.offset_y,
+            )
+        };
+        // retained_tile guarantees the source is present
+        let source = source.unwrap();
+
+        self.composite_with_scale_and_offset(&source, col, row, scale, offset_x, offset_y);
+        self.retained_tile(col, row)?.scale = scale;
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn reoffset_tile(
+        &mut self,
+        col: u32,
+        row: u32,
+        offset_x: i32,
+        offset_y: i32,
+    ) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+
+        let (source, scale) = {
+            let tile_info = self.retained_tile(col, row)?;
+            (tile_info.source.clone(), tile_info.scale)
+        };
+        // retained_tile guarantees the source is present
+        let source = source.unwrap();
+
+        self.composite_with_scale_and_offset(&source, col, row, scale, offset_x, offset_y);
+        let tile_info = self.retained_tile(col, row)?;
+        tile_info.offset_x = offset_x;
+        tile_info.offset_y = offset_y;
+
+        Ok(())
+    }
+
+    // Redraw the tile from its retained source as a fresh load would: scale 1.0, no offset, and
+    // no filters or opacity. Errors for tiles without a retained source, whose original pixels
+    // are gone.
+    #[wasm_bindgen]
+    pub fn reset_tile_transform(&mut self, col: u32, row: u32) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+
+        // retained_tile guarantees the source is present
+        let source = self.retained_tile(col, row)?.source.clone().unwrap();
+        self.load_decoded_with_scale((*source).clone(), col, row, 1.0, None, false);
+
+        Ok(())
+    }
+
+    // Helper method to reach a loaded tile's recorded draw rectangle
+    fn tile_drawn_mut(&mut self, col: u32, row: u32) -> Option<&mut [u32; 4]> {
+        self.loaded_tiles
+            .iter_mut()
+            .find(|tile| tile.col == col && tile.row == row && tile.has_image)
+            .and_then(|tile| tile.drawn.as_mut())
+    }
+
+    // Helper method to look up a loaded tile that still holds its decoded source image
+    fn retained_tile(&mut self, col: u32, row: u32) -> Result<&mut TileInfo, JsValue> {
+        self.loaded_tiles
+            .iter_mut()
+            .find(|tile| tile.col == col && tile.row == row && tile.source.is_some())
+            .ok_or_else(|| {
+                JsValue::from_str(&format!(
+                    "Tile ({}, {}) has no retained source image",
+                    col, row
+                ))
+            })
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_tile(&mut self, col: u32, row: u32) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+
+        // Drop the image from the tile but keep its per-tile settings
+        if let Some(tile_info) = self.take_tile_info(col, row) {
+            if tile_info.has_settings() {
+                self.loaded_tiles.push(tile_info.cleared());
+            }
+        }
+
+        // Clear the tile area by setting it to its background color
+        let background = self.tile_background(col, row);
+        self.fill_tile_region(col, row, background);
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn fill_tile_with_color(
+        &mut self,
+        col: u32,
+        row: u32,
+        r: u8,
+        g: u8,
+        b: u8,
+        a: u8,
+    ) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+
+        // Unlike clear_tile, the solid color counts as content so patterns skip it
+        self.mark_tile_loaded(col, row);
+        self.fill_tile_region(col, row, [r, g, b, a]);
+        self.draw_tile_border(col, row);
+
+        Ok(())
+    }
+
+    // Fill the tile with a linear gradient from the first color to the second, top to bottom
+    // when `vertical` and left to right otherwise, interpolating straight RGBA. Like a solid
+    // fill, the gradient counts as content.
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn fill_tile_gradient(
+        &mut self,
+        col: u32,
+        row: u32,
+        r1: u8,
+        g1: u8,
+        b1: u8,
+        a1: u8,
+        r2: u8,
+        g2: u8,
+        b2: u8,
+        a2: u8,
+        vertical: bool,
+    ) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+
+        self.mark_tile_loaded(col, row);
+        let (tile_width, tile_height) = self.tile_size(col, row);
+        let (start, end) = ([r1, g1, b1, a1], [r2, g2, b2, a2]);
+        let steps = if vertical { tile_height } else { tile_width }
+            .saturating_sub(1)
+            .max(1);
+        let tile_width = tile_width as usize;
+        let premultiplied = self.premultiplied;
+        let mut i = 0;
+        self.for_each_tile_pixel(col, row, |pixel| {
+            let (x, y) = (i % tile_width, i / tile_width);
+            i += 1;
+            let t = if vertical { y } else { x } as f32 / steps as f32;
+            for ((channel, &from), &to) in pixel.iter_mut().zip(&start).zip(&end) {
+                *channel = (from as f32 + (to as f32 - from as f32) * t).round() as u8;
+            }
+            if premultiplied {
+                premultiply_pixel(pixel);
+            }
+        });
+        self.draw_tile_border(col, row);
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_all_tiles(&mut self) {
+        self.loaded_tiles.clear();
+
+        // Nothing is loaded anymore, so the whole buffer becomes background in one pass
+        let background = self.stored_background();
+        for pixel in self.data.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&background);
+        }
+        self.mark_dirty(0, 0, self.width, self.height);
+    }
+
+    // Rotate the whole composed buffer clockwise by quarter turns (taken modulo 4). For odd
+    // turns the grid transposes: columns become rows and tile sizes swap. Tile state, retained
+    // sources and offsets follow their pixels; undo history, grid lines and any collage in
+    // progress are dropped.
+    #[wasm_bindgen]
+    pub fn rotate_canvas(&mut self, quarter_turns: u32) {
+        let turns = quarter_turns % 4;
+        if turns == 0 {
+            return;
+        }
+
+        let reversed = |sizes: &[u32]| sizes.iter().rev().copied().collect::<Vec<u32>>();
+        let (col_widths, row_heights) = match turns {
+            1 => (reversed(&self.row_heights), self.col_widths.clone()),
+            2 => (reversed(&self.col_widths), reversed(&self.row_heights)),
+            _ => (self.row_heights.clone(), reversed(&self.col_widths)),
+        };
+        let mut rotated = ImageBuffer::from_layout(col_widths, row_heights, self.gutter)
+            .expect("rotating keeps the buffer's size");
+        rotated.background_r = self.background_r;
+        rotated.background_g = self.background_g;
+        rotated.background_b = self.background_b;
+        rotated.background_a = self.background_a;
+        rotated.letterbox = self.letterbox;
+        rotated.premultiplied = self.premultiplied;
+
+        let canvas =
+            image::RgbaImage::from_raw(self.width, self.height, std::mem::take(&mut self.data))
+                .unwrap();
+        rotated.data = match turns {
+            1 => image::imageops::rotate90(&canvas),
+            2 => image::imageops::rotate180(&canvas),
+            _ => image::imageops::rotate270(&canvas),
+        }
+        .into_raw();
+
+        let (num_cols, num_rows) = (self.num_cols, self.num_rows);
+        for mut tile in std::mem::take(&mut self.loaded_tiles) {
+            let (tile_width, tile_height) = self.tile_size(tile.col, tile.row);
+            let (col, row) = (tile.col, tile.row);
+            (tile.col, tile.row) = match turns {
+                1 => (num_rows - 1 - row, col),
+                2 => (num_cols - 1 - col, num_rows - 1 - row),
+                _ => (row, num_cols - 1 - col),
+            };
+            let (offset_x, offset_y) = (tile.offset_x, tile.offset_y);
+            (tile.offset_x, tile.offset_y) = match turns {
+                1 => (-offset_y, offset_x),
+                2 => (-offset_x, -offset_y),
+                _ => (offset_y, -offset_x),
+            };
+            tile.drawn = tile.drawn.map(|[x, y, width, height]| match turns {
+                1 => [tile_height - y - height, x, height, width],
+                2 => [
+                    tile_width - x - width,
+                    tile_height - y - height,
+                    width,
+                    height,
+                ],
+                _ => [y, tile_width - x - width, height, width],
+            });
+            tile.source = tile.source.map(|source| {
+                Rc::new(match turns {
+                    1 => source.rotate90(),
+                    2 => source.rotate180(),
+                    _ => source.rotate270(),
+                })
+            });
+            rotated.loaded_tiles.push(tile);
+        }
+        rotated.mark_dirty(0, 0, rotated.width, rotated.height);
+
+        let proxy_factor = self.proxy_factor;
+        *self = rotated;
+        self.enable_proxy(proxy_factor);
+    }
+
+    // Add or remove columns and rows in place. Tiles that still fit keep their pixels and state;
+    // new cells use the nominal tile size and start as background.
+    #[wasm_bindgen]
+    pub fn resize_grid(&mut self, new_cols: u32, new_rows: u32) -> Result<(), JsValue> {
+        let mut col_widths = self.col_widths.clone();
+        col_widths.resize(new_cols as usize, self.tile_width);
+        let mut row_heights = self.row_heights.clone();
+        row_heights.resize(new_rows as usize, self.tile_height);
+
+        let mut resized = ImageBuffer::from_layout(col_widths, row_heights, self.gutter)?;
+        resized.tile_width = self.tile_width;
+        resized.tile_height = self.tile_height;
+        resized.background_r = self.background_r;
+        resized.background_g = self.background_g;
+        resized.background_b = self.background_b;
+        resized.background_a = self.background_a;
+        resized.letterbox = self.letterbox;
+        resized.premultiplied = self.premultiplied;
+        resized.clear_all_tiles();
+
+        // Surviving tiles keep their column width and row height, so regions copy straight over
+        for row in 0..new_rows.min(self.num_rows) {
+            for col in 0..new_cols.min(self.num_cols) {
+                resized.write_tile_region(col, row, &self.read_tile_region(col, row));
+            }
+        }
+        resized.loaded_tiles = std::mem::take(&mut self.loaded_tiles)
+            .into_iter()
+            .filter(|tile| tile.col < new_cols && tile.row < new_rows)
+            .collect();
+
+        let proxy_factor = self.proxy_factor;
+        *self = resized;
+        self.enable_proxy(proxy_factor);
+
+        Ok(())
+    }
+
+    // Switch every cell to the given size and reflow the buffer. Tiles with a retained source
+    // are re-composited from it at their stored scale, offset and opacity (other filters are
+    // lost); tiles without one have their current pixels rescaled with nearest-neighbor.
+    #[wasm_bindgen]
+    pub fn set_tile_size(
+        &mut self,
+        new_tile_width: u32,
+        new_tile_height: u32,
+    ) -> Result<(), JsValue> {
+        let mut resized = ImageBuffer::from_layout(
+            vec![new_tile_width; self.num_cols as usize],
+            vec![new_tile_height; self.num_rows as usize],
+            self.gutter,
+        )?;
+        resized.background_r = self.background_r;
+        resized.background_g = self.background_g;
+        resized.background_b = self.background_b;
+        resized.background_a = self.background_a;
+        resized.letterbox = self.letterbox;
+        resized.premultiplied = self.premultiplied;
+        resized.clear_all_tiles();
+        resized.loaded_tiles = self.loaded_tiles.clone();
+
+        for tile_info in &self.loaded_tiles {
+            let (col, row) = (tile_info.col, tile_info.row);
+            if !tile_info.has_image {
+                let background = resized.tile_background(col, row);
+                resized.fill_tile_region(col, row, background);
+                continue;
+            }
+
+            match &tile_info.source {
+                Some(source) => {
+                    resized.composite_with_scale_and_offset(
+                        source,
+                        col,
+                        row,
+                        tile_info.scale,
+                        tile_info.offset_x,
+                        tile_info.offset_y,
+                    );
+                    let opacity = tile_info.opacity;
+                    if opacity < 1.0 {
+                        resized.for_each_tile_pixel(col, row, |pixel| {
+                            pixel[3] = (pixel[3] as f32 * opacity).round() as u8;
+                        });
+                    }
+                }
+                None => {
+                    let (tile_width, tile_height) = self.tile_size(col, row);
+                    let tile_img = image::RgbaImage::from_raw(
+                        tile_width,
+                        tile_height,
+                        self.read_tile_region(col, row),
+                    )
+                    .unwrap();
+                    let rescaled = image::imageops::resize(
+                        &tile_img,
+                        new_tile_width,
+                        new_tile_height,
+                        ResizeFilter::Nearest.filter_type(),
+                    );
+                    resized.composite_into_tile(
+                        col,
+                        row,
+                        rescaled.as_raw(),
+                        new_tile_width,
+                        new_tile_height,
+                        0,
+                        0,
+                    );
+                }
+            }
+        }
+
+        let proxy_factor = self.proxy_factor;
+        *self = resized;
+        self.enable_proxy(proxy_factor);
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn flip_tile_horizontal(&mut self, col: u32, row: u32) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+
+        self.mark_tile_dirty(col, row);
+        let (tile_start_x, tile_start_y) = self.tile_origin(col, row);
+        let (tile_width, tile_height) = self.tile_size(col, row);
+        let tile_width = tile_width as usize;
+
+        // Mirror each row of the tile by swapping pixels from the outside in
+        for y in 0..tile_height as usize {
+            let row_start = (tile_start_y + y) * self.width as usize + tile_start_x;
+            for x in 0..tile_width / 2 {
+                let left = (row_start + x) * 4;
+                let right = (row_start + tile_width - 1 - x) * 4;
+                for channel in 0..4 {
+                    self.data.swap(left + channel, right + channel);
+                }
+            }
+        }
+        if let Some([x, _, width, _]) = self.tile_drawn_mut(col, row) {
+            *x = tile_width as u32 - *x - *width;
+        }
+
+        self.mark_tile_modified(col, row);
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn flip_tile_vertical(&mut self, col: u32, row: u32) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+
+        self.mark_tile_dirty(col, row);
+        let (tile_start_x, tile_start_y) = self.tile_origin(col, row);
+        let (tile_width, tile_height) = self.tile_size(col, row);
+        let tile_height = tile_height as usize;
+        let row_len = tile_width as usize * 4;
+
+        // Swap whole tile rows from the outside in
+        for y in 0..tile_height / 2 {
+            let top = ((tile_start_y + y) * self.width as usize + tile_start_x) * 4;
+            let bottom =
+                ((tile_start_y + tile_height - 1 - y) * self.width as usize + tile_start_x) * 4;
+            let (upper, lower) = self.data.split_at_mut(bottom);
+            upper[top..top + row_len].swap_with_slice(&mut lower[..row_len]);
+        }
+        if let Some([_, y, _, height]) = self.tile_drawn_mut(col, row) {
+            *y = tile_height as u32 - *y - *height;
+        }
+
+        self.mark_tile_modified(col, row);
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn rotate_tile(&mut self, col: u32, row: u32, quarter_turns: u32) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        let (tile_width, tile_height) = self.tile_size(col, row);
+
+        let turns = quarter_turns % 4;
+        if turns == 0 {
+            return Ok(());
+        }
+
+        let tile_img =
+            image::RgbaImage::from_raw(tile_width, tile_height, self.read_tile_region(col, row))
+                .unwrap();
+        let rotated = match turns {
+            1 => image::imageops::rotate90(&tile_img),
+            2 => image::imageops::rotate180(&tile_img),
+            _ => image::imageops::rotate270(&tile_img),
+        };
+
+        // Quarter turns of a rectangular tile swap its dimensions, so re-fit the result
+        let rotated = if rotated.dimensions() == (tile_width, tile_height) {
+            rotated
+        } else {
+            let rotated = DynamicImage::ImageRgba8(rotated);
+            let filter = fit_filter(&rotated, tile_width, tile_height);
+            resize_preserve_aspect_ratio(&rotated, tile_width, tile_height, filter).to_rgba8()
+        };
+        let (actual_width, actual_height) = rotated.dimensions();
+
+        // Center the rotated content, filling any leftover space with background
+        self.composite_into_tile(
+            col,
+            row,
+            rotated.as_raw(),
+            actual_width,
+            actual_height,
+            (tile_width as i32 - actual_width as i32) / 2,
+            (tile_height as i32 - actual_height as i32) / 2,
+        );
+
+        self.mark_tile_modified(col, row);
+
+        Ok(())
+    }
+
+    // Rotate the tile's current pixels clockwise by an arbitrary angle about its center. Each
+    // output pixel is inverse-mapped into the original and sampled bilinearly (clamping at the
+    // tile edge); corners the rotation exposes become background. Multiples of 360 are a no-op.
+    #[wasm_bindgen]
+    pub fn rotate_tile_angle(&mut self, col: u32, row: u32, degrees: f32) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        if !degrees.is_finite() {
+            return Err(JsValue::from_str(&format!(
+                "Rotation angle must be finite, got {}",
+                degrees
+            )));
+        }
+        if degrees % 360.0 == 0.0 {
+            return Ok(());
+        }
+
+        let (tile_width, tile_height) = self.tile_size(col, row);
+        let source = self.read_tile_region(col, row);
+        let background = self.stored_tile_background(col, row);
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let (center_x, center_y) = (tile_width as f32 / 2.0, tile_height as f32 / 2.0);
+        let (width, height) = (tile_width as usize, tile_height as usize);
+        let texel = |x: usize, y: usize| &source[(y * width + x) * 4..(y * width + x) * 4 + 4];
+
+        let mut rotated = Vec::with_capacity(source.len());
+        for y in 0..height {
+            for x in 0..width {
+                // Undo the rotation to find where this pixel's center came from
+                let dx = x as f32 + 0.5 - center_x;
+                let dy = y as f32 + 0.5 - center_y;
+                let src_x = center_x + dx * cos + dy * sin;
+                let src_y = center_y - dx * sin + dy * cos;
+                if src_x < 0.0
+                    || src_y < 0.0
+                    || src_x > tile_width as f32
+                    || src_y > tile_height as f32
+                {
+                    rotated.extend_from_slice(&background);
+                    continue;
+                }
+
+                // Bilinear blend of the four nearest pixel centers, clamped to the tile
+                let fx = (src_x - 0.5).max(0.0);
+                let fy = (src_y - 0.5).max(0.0);
+                let x0 = (fx as usize).min(width - 1);
+                let y0 = (fy as usize).min(height - 1);
+                let x1 = (x0 + 1).min(width - 1);
+                let y1 = (y0 + 1).min(height - 1);
+                let (tx, ty) = (fx - x0 as f32, fy - y0 as f32);
+                for channel in 0..4 {
+                    let top = texel(x0, y0)[channel] as f32 * (1.0 - tx)
+                        + texel(x1, y0)[channel] as f32 * tx;
+                    let bottom = texel(x0, y1)[channel] as f32 * (1.0 - tx)
+                        + texel(x1, y1)[channel] as f32 * tx;
+                    rotated.push((top * (1.0 - ty) + bottom * ty).round().clamp(0.0, 255.0) as u8);
+                }
+            }
+        }
+        self.write_tile_region(col, row, &rotated);
+        self.draw_tile_border(col, row);
+
+        // The drawn rectangle grows to the bounding box of its rotated corners
+        if let Some(drawn) = self.tile_drawn_mut(col, row) {
+            let [x, y, w, h] = *drawn;
+            let corners = [(x, y), (x + w, y), (x, y + h), (x + w, y + h)].map(|(cx, cy)| {
+                let (dx, dy) = (cx as f32 - center_x, cy as f32 - center_y);
+                (
+                    center_x + dx * cos - dy * sin,
+                    center_y + dx * sin + dy * cos,
+                )
+            });
+            let min_x = corners.iter().map(|c| c.0).fold(f32::MAX, f32::min);
+            let max_x = corners.iter().map(|c| c.0).fold(f32::MIN, f32::max);
+            let min_y = corners.iter().map(|c| c.1).fold(f32::MAX, f32::min);
+            let max_y = corners.iter().map(|c| c.1).fold(f32::MIN, f32::max);
+            let x0 = min_x.floor().clamp(0.0, tile_width as f32) as u32;
+            let y0 = min_y.floor().clamp(0.0, tile_height as f32) as u32;
+            let x1 = max_x.ceil().clamp(x0 as f32, tile_width as f32) as u32;
+            let y1 = max_y.ceil().clamp(y0 as f32, tile_height as f32) as u32;
+            *drawn = [x0, y0, x1 - x0, y1 - y0];
+        }
+
+        self.mark_tile_modified(col, row);
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_tile_opacity(&mut self, col: u32, row: u32, opacity: f32) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+
+        // This scales the current alpha, so repeated calls compound until the next load
+        let opacity = opacity.clamp(0.0, 1.0);
+        let premultiplied = self.premultiplied;
+        self.for_each_tile_pixel(col, row, |pixel| scale_alpha(pixel, opacity, premultiplied));
+        self.tile_info_mut(col, row).opacity *= opacity;
+        self.mark_tile_modified(col, row);
+
+        Ok(())
+    }
+
+    // Keep only the tile-relative rectangle (x, y, width, height) of the current pixels in place;
+    // everything else in the tile becomes background
+    #[wasm_bindgen]
+    pub fn crop_tile(
+        &mut self,
+        col: u32,
+        row: u32,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        let (tile_width, tile_height) = self.tile_size(col, row);
+        let fits = x.checked_add(width).is_some_and(|end| end <= tile_width)
+            && y.checked_add(height).is_some_and(|end| end <= tile_height);
+        if !fits {
+            return Err(JsValue::from_str(&format!(
+                "Crop rect ({}, {}, {}x{}) is outside the {}x{} tile",
+                x, y, width, height, tile_width, tile_height
+            )));
+        }
+
+        // The visible image shrinks to its overlap with the crop rect
+        if let Some(tile_info) = self
+            .loaded_tiles
+            .iter_mut()
+            .find(|tile| tile.col == col && tile.row == row && tile.has_image)
+        {
+            let [draw_x, draw_y, draw_width, draw_height] =
+                tile_info.drawn.unwrap_or([0, 0, tile_width, tile_height]);
+            let x0 = draw_x.max(x);
+            let y0 = draw_y.max(y);
+            let x1 = (draw_x + draw_width).min(x + width).max(x0);
+            let y1 = (draw_y + draw_height).min(y + height).max(y0);
+            tile_info.drawn = Some([x0, y0, x1 - x0, y1 - y0]);
+        }
+
+        let background = self.stored_tile_background(col, row);
+        let (x, y) = (x as usize, y as usize);
+        let (crop_width, crop_height) = (width as usize, height as usize);
+        let mut i = 0;
+        self.for_each_tile_pixel(col, row, |pixel| {
+            let (px, py) = (i % tile_width as usize, i / tile_width as usize);
+            let inside = px >= x && px < x + crop_width && py >= y && py < y + crop_height;
+            if !inside {
+                pixel.copy_from_slice(&background);
+            }
+            i += 1;
+        });
+        self.draw_tile_border(col, row);
+
+        self.mark_tile_modified(col, row);
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn grayscale_tile(&mut self, col: u32, row: u32) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+
+        self.for_each_tile_pixel(col, row, |pixel| {
+            let gray = luminance(pixel).round() as u8;
+            pixel[0] = gray;
+            pixel[1] = gray;
+            pixel[2] = gray;
+        });
+
+        self.mark_tile_modified(col, row);
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn adjust_tile(
+        &mut self,
+        col: u32,
+        row: u32,
+        brightness: f32,
+        contrast: f32,
+    ) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+
+        // Contrast pivots around mid-gray, brightness shifts by a fraction of full range
+        let offset = 128.0 + brightness * 255.0;
+        self.for_each_tile_pixel(col, row, |pixel| {
+            for channel in pixel.iter_mut().take(3) {
+                let adjusted = (*channel as f32 - 128.0) * contrast + offset;
+                *channel = adjusted.round().clamp(0.0, 255.0) as u8;
+            }
+        });
+
+        self.mark_tile_modified(col, row);
+
+        Ok(())
+    }
+
+    // Remap the tile's RGB channels through a levels curve: values are stretched from the input
+    // black/white points to 0..1, raised to 1/gamma (above 1 brightens midtones), then scaled
+    // into the output range. Alpha is untouched.
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn apply_levels_tile(
+        &mut self,
+        col: u32,
+        row: u32,
+        in_black: u8,
+        in_white: u8,
+        gamma: f32,
+        out_black: u8,
+        out_white: u8,
+    ) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        if in_white <= in_black {
+            return Err(JsValue::from_str(&format!(
+                "Input white point {} must be above the black point {}",
+                in_white, in_black
+            )));
+        }
+        if !(gamma.is_finite() && gamma > 0.0) {
+            return Err(JsValue::from_str(&format!(
+                "Gamma must be a positive number, got {}",
+                gamma
+            )));
+        }
+
+        let input_range = (in_white - in_black) as f32;
+        let output_range = out_white as f32 - out_black as f32;
+        let lookup: Vec<u8> = (0..=255u8)
+            .map(|value| {
+                let normalized = ((value as f32 - in_black as f32) / input_range).clamp(0.0, 1.0);
+                let curved = normalized.powf(1.0 / gamma);
+                (out_black as f32 + curved * output_range)
+                    .round()
+                    .clamp(0.0, 255.0) as u8
+            })
+            .collect();
+
+        self.for_each_tile_pixel(col, row, |pixel| {
+            for channel in pixel.iter_mut().take(3) {
+                *channel = lookup[*channel as usize];
+            }
+        });
+
+        self.mark_tile_modified(col, row);
+
+        Ok(())
+    }
+
+    // Quantize each RGB channel to `levels` evenly spaced values for a poster effect, leaving
+    // alpha alone. A u8 can't hold 256, so 0 stands for it and leaves the tile unchanged; 1 is
+    // raised to 2.
+    #[wasm_bindgen]
+    pub fn posterize_tile(&mut self, col: u32, row: u32, levels: u8) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        if levels == 0 {
+            return Ok(());
+        }
+
+        let step = 255.0 / (levels.max(2) - 1) as f32;
+        let lookup: Vec<u8> = (0..=255u8)
+            .map(|value| ((value as f32 / step).round() * step).round() as u8)
+            .collect();
+        self.for_each_tile_pixel(col, row, |pixel| {
+            for channel in pixel.iter_mut().take(3) {
+                *channel = lookup[*channel as usize];
+            }
+        });
+
+        self.mark_tile_modified(col, row);
+
+        Ok(())
+    }
+
+    // Turn each pixel white if its luminance is above `level` and black otherwise, for
+    // stencils and masks. Alpha is left alone.
+    #[wasm_bindgen]
+    pub fn threshold_tile(&mut self, col: u32, row: u32, level: u8) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+
+        self.for_each_tile_pixel(col, row, |pixel| {
+            let value = if luminance(pixel) > level as f32 {
+                255
+            } else {
+                0
+            };
+            pixel[..3].fill(value);
+        });
+
+        self.mark_tile_modified(col, row);
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn blur_tile(&mut self, col: u32, row: u32, radius: u32) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        let (tile_width, tile_height) = self.tile_size(col, row);
+        // Past the tile's longer side every window already spans the whole line
+        let radius = radius.min(tile_width.max(tile_height));
+
+        if radius == 0 {
+            return Ok(());
+        }
+
+        // Blur a copy of the tile so edge pixels only ever sample this tile
+        let mut pixels = self.read_tile_region(col, row);
+        box_blur(&mut pixels, tile_width, tile_height, 4, radius);
+        self.write_tile_region(col, row, &pixels);
+
+        self.mark_tile_modified(col, row);
+
+        Ok(())
+    }
+
+    // Replace each `block` x `block` square of the tile with its average color, e.g. to redact
+    // faces. Squares cut off at the right and bottom edges average just their partial area.
+    #[wasm_bindgen]
+    pub fn pixelate_tile(&mut self, col: u32, row: u32, block: u32) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        let (tile_width, tile_height) = self.tile_size(col, row);
+
+        if block <= 1 {
+            return Ok(());
+        }
+
+        let mut pixels = self.read_tile_region(col, row);
+        let (width, height, block) = (tile_width as usize, tile_height as usize, block as usize);
+        for block_y in (0..height).step_by(block) {
+            for block_x in (0..width).step_by(block) {
+                let rows = block_y..(block_y + block).min(height);
+                let cols = block_x..(block_x + block).min(width);
+                let count = (rows.len() * cols.len()) as u32;
+
+                let mut sums = [0u32; 4];
+                for y in rows.clone() {
+                    let start = (y * width + cols.start) * 4;
+                    for pixel in pixels[start..start + cols.len() * 4].chunks_exact(4) {
+                        for (sum, &channel) in sums.iter_mut().zip(pixel) {
+                            *sum += channel as u32;
+                        }
+                    }
+                }
+                let average = sums.map(|sum| ((sum + count / 2) / count) as u8);
+
+                for y in rows {
+                    let start = (y * width + cols.start) * 4;
+                    for pixel in pixels[start..start + cols.len() * 4].chunks_exact_mut(4) {
+                        pixel.copy_from_slice(&average);
+                    }
+                }
+            }
+        }
+        self.write_tile_region(col, row, &pixels);
+
+        self.mark_tile_modified(col, row);
+
+        Ok(())
+    }
+
+    // Make pixels within `tolerance` (per channel) of the key color fully transparent
+    #[wasm_bindgen]
+    pub fn remove_color_key(
+        &mut self,
+        col: u32,
+        row: u32,
+        r: u8,
+        g: u8,
+        b: u8,
+        tolerance: u8,
+    ) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+
+        let key = [r, g, b];
+        let premultiplied = self.premultiplied;
+        self.for_each_tile_pixel(col, row, |pixel| {
+            // The key is a straight color, so compare against the pixel's straight color
+            let mut straight: [u8; 4] = (&*pixel).try_into().unwrap();
+            if premultiplied {
+                unpremultiply_pixel(&mut straight);
+            }
+            let matches = straight
+                .iter()
+                .zip(&key)
+                .all(|(&value, &key)| value.abs_diff(key) <= tolerance);
+            if matches {
+                scale_alpha(pixel, 0.0, premultiplied);
+            }
+        });
+
+        self.mark_tile_modified(col, row);
+
+        Ok(())
+    }
+
+    // Multiply each color channel by (r, g, b) / 255, leaving alpha alone
+    #[wasm_bindgen]
+    pub fn tint_tile(&mut self, col: u32, row: u32, r: u8, g: u8, b: u8) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+
+        let tint = [r, g, b];
+        self.for_each_tile_pixel(col, row, |pixel| {
+            for (channel, &factor) in pixel.iter_mut().zip(&tint) {
+                *channel = (*channel as f32 * factor as f32 / 255.0).round() as u8;
+            }
+        });
+
+        self.mark_tile_modified(col, row);
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn sharpen_tile(&mut self, col: u32, row: u32, amount: f32) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        let (tile_width, tile_height) = self.tile_size(col, row);
+
+        if amount == 0.0 {
+            return Ok(());
+        }
+
+        // Sharpen a copy of the tile so edge pixels only ever sample this tile
+        let mut pixels = self.read_tile_region(col, row);
+        sharpen(&mut pixels, tile_width, tile_height, amount);
+        self.write_tile_region(col, row, &pixels);
+
+        self.mark_tile_modified(col, row);
+
+        Ok(())
+    }
+
+    // Replace the tile with the Sobel gradient magnitude of its luminance, as grayscale line art
+    // with alpha preserved. This is a full 3x3 convolution per pixel, so it's meant for final
+    // renders rather than live dragging.
+    #[wasm_bindgen]
+    pub fn edge_detect_tile(&mut self, col: u32, row: u32) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        let (tile_width, tile_height) = self.tile_size(col, row);
+
+        // Work on a copy of the tile so edge pixels only ever sample this tile
+        let mut pixels = self.read_tile_region(col, row);
+        sobel(&mut pixels, tile_width, tile_height);
+        self.write_tile_region(col, row, &pixels);
+
+        self.mark_tile_modified(col, row);
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn invert_tile(&mut self, col: u32, row: u32) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+
+        self.for_each_tile_pixel(col, row, |pixel| {
+            for channel in pixel.iter_mut().take(3) {
+                *channel = 255 - *channel;
+            }
+        });
+
+        self.mark_tile_modified(col, row);
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn sepia_tile(&mut self, col: u32, row: u32) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+
+        self.for_each_tile_pixel(col, row, |pixel| {
+            let (r, g, b) = (pixel[0] as f32, pixel[1] as f32, pixel[2] as f32);
+            let toned = [
+                0.393 * r + 0.769 * g + 0.189 * b,
+                0.349 * r + 0.686 * g + 0.168 * b,
+                0.272 * r + 0.534 * g + 0.131 * b,
+            ];
+            for (channel, value) in pixel.iter_mut().zip(toned) {
+                *channel = value.round().clamp(0.0, 255.0) as u8;
+            }
+        });
+
+        self.mark_tile_modified(col, row);
+
+        Ok(())
+    }
+
+    // Fade the edges of the tile's drawn image into the tile background. Pixels within `radius`
+    // of the drawn rectangle's boundary are mixed with the background along a linear alpha ramp.
+    // Tiles without an image, and a radius of 0, are left unchanged.
+    #[wasm_bindgen]
+    pub fn feather_tile(&mut self, col: u32, row: u32, radius: u32) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+
+        let Some(tile_info) = self
+            .tile_info(col, row)
+            .filter(|tile| tile.has_image && radius > 0)
+        else {
+            return Ok(());
+        };
+        let (tile_width, tile_height) = self.tile_size(col, row);
+        let [draw_x, draw_y, draw_width, draw_height] =
+            tile_info.drawn.unwrap_or([0, 0, tile_width, tile_height]);
+        let background = self.stored_tile_background(col, row);
+
+        let (tile_start_x, tile_start_y) = self.tile_origin(col, row);
+        self.mark_dirty(
+            tile_start_x as u32 + draw_x,
+            tile_start_y as u32 + draw_y,
+            draw_width,
+            draw_height,
+        );
+        for y in draw_y..draw_y + draw_height {
+            for x in draw_x..draw_x + draw_width {
+                // Distance to the nearest edge of the drawn rectangle
+                let edge_distance = (x - draw_x)
+                    .min(y - draw_y)
+                    .min(draw_x + draw_width - 1 - x)
+                    .min(draw_y + draw_height - 1 - y);
+                if edge_distance >= radius {
+                    continue;
+                }
+                let weight = (edge_distance as f32 + 0.5) / radius as f32;
+
+                let index =
+                    ((tile_start_y + y as usize) * self.width as usize + tile_start_x + x as usize)
+                        * 4;
+                for (channel, &back) in self.data[index..index + 4].iter_mut().zip(&background) {
+                    *channel =
+                        (back as f32 + (*channel as f32 - back as f32) * weight).round() as u8;
+                }
+            }
+        }
+        self.mark_tile_modified(col, row);
+
+        Ok(())
+    }
+
+    // Darken the tile radially from its center toward the corners. `strength` runs from 0 (no
+    // change) to 1 (corners fully black) with a smoothstep falloff; alpha is left untouched.
+    #[wasm_bindgen]
+    pub fn vignette_tile(&mut self, col: u32, row: u32, strength: f32) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+
+        if strength.is_nan() || strength <= 0.0 {
+            return Ok(());
+        }
+        let strength = strength.min(1.0);
+
+        self.mark_tile_dirty(col, row);
+        let (tile_start_x, tile_start_y) = self.tile_origin(col, row);
+        let (tile_width, tile_height) = self.tile_size(col, row);
+        let (half_w, half_h) = (tile_width as f32 / 2.0, tile_height as f32 / 2.0);
+        let max_distance = (half_w * half_w + half_h * half_h).sqrt();
+
+        for y in 0..tile_height as usize {
+            for x in 0..tile_width as usize {
+                // Distance of the pixel center from the tile center, normalized to 1 at the corners
+                let dx = x as f32 + 0.5 - half_w;
+                let dy = y as f32 + 0.5 - half_h;
+                let t = ((dx * dx + dy * dy).sqrt() / max_distance).min(1.0);
+                let factor = 1.0 - strength * t * t * (3.0 - 2.0 * t);
+
+                let index = ((tile_start_y + y) * self.width as usize + tile_start_x + x) * 4;
+                for channel in &mut self.data[index..index + 3] {
+                    *channel = (*channel as f32 * factor).round() as u8;
+                }
+            }
+        }
+
+        self.mark_tile_modified(col, row);
+
+        Ok(())
+    }
+
+    // Alpha-blend a solid bar across the bottom `height` rows of the tile, as a backdrop for
+    // captions overlaid by the caller. Like the shadow, it is painted once, not retained.
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_caption_bar(
+        &mut self,
+        col: u32,
+        row: u32,
+        height: u32,
+        r: u8,
+        g: u8,
+        b: u8,
+        a: u8,
+    ) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        let (tile_width, tile_height) = self.tile_size(col, row);
+        if height > tile_height {
+            return Err(JsValue::from_str(&format!(
+                "Caption bar height {} exceeds tile height {}",
+                height, tile_height
+            )));
+        }
+
+        let (tile_start_x, tile_start_y) = self.tile_origin(col, row);
+        let bar_y = tile_start_y as u32 + tile_height - height;
+        self.mark_dirty(tile_start_x as u32, bar_y, tile_width, height);
+
+        let mut color = [r, g, b, a];
+        self.match_buffer_alpha(&mut color);
+        let blend = self.source_over_fn();
+        for y in bar_y as usize..bar_y as usize + height as usize {
+            let start = (y * self.width as usize + tile_start_x) * 4;
+            for pixel in self.data[start..start + tile_width as usize * 4].chunks_exact_mut(4) {
+                let blended = blend(&color, pixel);
+                pixel.copy_from_slice(&blended);
+            }
+        }
+
+        self.mark_tile_modified(col, row);
+
+        Ok(())
+    }
+
+    // Mask the tile's corners outside quarter-circles of the given radius to transparent,
+    // anti-aliasing the curved edge. The radius is clamped to half the smaller tile side.
+    #[wasm_bindgen]
+    pub fn apply_rounded_corners(
+        &mut self,
+        col: u32,
+        row: u32,
+        radius: u32,
+    ) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        let (tile_width, tile_height) = self.tile_size(col, row);
+
+        let radius = radius.min(tile_width.min(tile_height) / 2) as f32;
+        if radius == 0.0 {
+            return Ok(());
+        }
+
+        let (width, height) = (tile_width as f32, tile_height as f32);
+        let premultiplied = self.premultiplied;
+        let mut i = 0;
+        self.for_each_tile_pixel(col, row, |pixel| {
+            // Pixel center, and its distance into the nearest corner square
+            let x = (i % tile_width) as f32 + 0.5;
+            let y = (i / tile_width) as f32 + 0.5;
+            i += 1;
+            let dx = (radius - x).max(x - (width - radius));
+            let dy = (radius - y).max(y - (height - radius));
+            if dx <= 0.0 || dy <= 0.0 {
+                return;
+            }
+
+            let coverage = (radius - (dx * dx + dy * dy).sqrt() + 0.5).clamp(0.0, 1.0);
+            scale_alpha(pixel, coverage, premultiplied);
+        });
+
+        self.mark_tile_modified(col, row);
+
+        Ok(())
+    }
+
+    // Render a soft shadow of the tile's opaque pixels, shifted by the offset and blurred by
+    // `blur`. The shadow bleeds into gutters and neighboring tiles (clipped at the buffer edges)
+    // and sits beneath the tile's own pixels. It is painted once, not retained: anything that
+    // re-composites the affected area paints over it.
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_tile_shadow(
+        &mut self,
+        col: u32,
+        row: u32,
+        offset_x: i32,
+        offset_y: i32,
+        blur: u32,
+        r: u8,
+        g: u8,
+        b: u8,
+        a: u8,
+    ) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(col, row)?;
+        let (tile_start_x, tile_start_y) = self.tile_origin(col, row);
+        let (tile_width, tile_height) = self.tile_size(col, row);
+
+        // A blur wider than the tile only flattens the shadow further, and an offset past the
+        // buffer plus the margin already puts the whole shadow off-canvas
+        let blur = blur.min(tile_width.max(tile_height));
+        let reach_x = self.width as i32 + blur as i32;
+        let reach_y = self.height as i32 + blur as i32;
+        let offset_x = offset_x.clamp(-reach_x, reach_x);
+        let offset_y = offset_y.clamp(-reach_y, reach_y);
+
+        // Alpha mask of the tile, padded so the blur can spread past its edges
+        let margin = blur as usize;
+        let mask_width = tile_width as usize + 2 * margin;
+        let mask_height = tile_height as usize + 2 * margin;
+        let mut mask = vec![0u8; mask_width * mask_height];
+        for (y, range) in self.tile_row_ranges(col, row).enumerate() {
+            for (x, pixel) in self.data[range].chunks_exact(4).enumerate() {
+                mask[(y + margin) * mask_width + x + margin] = pixel[3];
+            }
+        }
+        box_blur(&mut mask, mask_width as u32, mask_height as u32, 1, blur);
+
+        let mask_x = tile_start_x as i64 + offset_x as i64 - margin as i64;
+        let mask_y = tile_start_y as i64 + offset_y as i64 - margin as i64;
+        let tile_x = tile_start_x..tile_start_x + tile_width as usize;
+        let tile_y = tile_start_y..tile_start_y + tile_height as usize;
+        let (buffer_width, buffer_height) = (self.width as i64, self.height as i64);
+        let blend = self.source_over_fn();
+
+        for (i, &coverage) in mask.iter().enumerate() {
+            let x = mask_x + (i % mask_width) as i64;
+            let y = mask_y + (i / mask_width) as i64;
+            if coverage == 0 || x < 0 || y < 0 || x >= buffer_width || y >= buffer_height {
+                continue;
+            }
+
+            let mut shadow = [r, g, b, (a as u32 * coverage as u32 / 255) as u8];
+            self.match_buffer_alpha(&mut shadow);
+            let (x, y) = (x as usize, y as usize);
+            let index = (y * self.width as usize + x) * 4;
+            let current = &self.data[index..index + 4];
+            let blended = if tile_x.contains(&x) && tile_y.contains(&y) {
+                // Keep the tile itself in front of its shadow
+                blend(current, &shadow)
+            } else {
+                blend(&shadow, current)
+            };
+            self.data[index..index + 4].copy_from_slice(&blended);
+        }
+
+        // Dirty region is the shadow's footprint clipped to the buffer
+        let x0 = mask_x.clamp(0, buffer_width);
+        let y0 = mask_y.clamp(0, buffer_height);
+        let x1 = (mask_x + mask_width as i64).clamp(0, buffer_width);
+        let y1 = (mask_y + mask_height as i64).clamp(0, buffer_height);
+        self.mark_dirty(x0 as u32, y0 as u32, (x1 - x0) as u32, (y1 - y0) as u32);
+        self.mark_tile_modified(col, row);
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn copy_tile(
+        &mut self,
+        src_col: u32,
+        src_row: u32,
+        dst_col: u32,
+        dst_row: u32,
+    ) -> Result<(), JsValue> {
+        // Validate both tile positions
+        self.validate_tile_position(src_col, src_row)?;
+        self.validate_tile_unlocked(dst_col, dst_row)?;
+
+        if (src_col, src_row) == (dst_col, dst_row) {
+            return Ok(());
+        }
+        self.validate_same_tile_size(src_col, src_row, dst_col, dst_row)?;
+
+        let pixels = self.read_tile_region(src_col, src_row);
+        self.write_tile_region(dst_col, dst_row, &pixels);
+
+        // The destination takes on the source image (so it can be rescaled too) but keeps
+        // its own per-tile settings
+        let src_info = self
+            .tile_info(src_col, src_row)
+            .cloned()
+            .unwrap_or_else(|| TileInfo::empty(src_col, src_row));
+        let dst_info = self
+            .take_tile_info(dst_col, dst_row)
+            .unwrap_or_else(|| TileInfo::empty(dst_col, dst_row));
+        let mut copied = dst_info.with_image_from(&src_info);
+        copied.has_image = true;
+        self.restore_tile_info(copied);
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn swap_tiles(
+        &mut self,
+        col_a: u32,
+        row_a: u32,
+        col_b: u32,
+        row_b: u32,
+    ) -> Result<(), JsValue> {
+        // Validate both tile positions
+        self.validate_tile_unlocked(col_a, row_a)?;
+        self.validate_tile_unlocked(col_b, row_b)?;
+
+        if (col_a, row_a) == (col_b, row_b) {
+            return Ok(());
+        }
+        self.validate_same_tile_size(col_a, row_a, col_b, row_b)?;
+
+        let pixels_a = self.read_tile_region(col_a, row_a);
+        let pixels_b = self.read_tile_region(col_b, row_b);
+        self.write_tile_region(col_a, row_a, &pixels_b);
+        self.write_tile_region(col_b, row_b, &pixels_a);
+
+        // Loaded state follows the pixels; per-tile settings stay with their cells
+        let info_a = self
+            .take_tile_info(col_a, row_a)
+            .unwrap_or_else(|| TileInfo::empty(col_a, row_a));
+        let info_b = self
+            .take_tile_info(col_b, row_b)
+            .unwrap_or_else(|| TileInfo::empty(col_b, row_b));
+        self.restore_tile_info(info_a.with_image_from(&info_b));
+        self.restore_tile_info(info_b.with_image_from(&info_a));
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn move_tile(
+        &mut self,
+        src_col: u32,
+        src_row: u32,
+        dst_col: u32,
+        dst_row: u32,
+    ) -> Result<(), JsValue> {
+        // Unlike swap_tiles, the origin always ends up empty. Check it can be cleared before
+        // copying, so a locked origin doesn't leave the destination overwritten.
+        if (src_col, src_row) != (dst_col, dst_row) {
+            self.validate_tile_unlocked(src_col, src_row)?;
+        }
+        self.copy_tile(src_col, src_row, dst_col, dst_row)?;
+        if (src_col, src_row) != (dst_col, dst_row) {
+            self.clear_tile(src_col, src_row)?;
+        }
+
+        Ok(())
+    }
+
+    // Helper method to put back a tile info entry unless it carries nothing worth tracking
+    fn restore_tile_info(&mut self, tile_info: TileInfo) {
+        if tile_info.has_image || tile_info.has_settings() {
+            self.loaded_tiles.push(tile_info);
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn is_tile_loaded(&self, col: u32, row: u32) -> bool {
+        self.loaded_tiles
+            .iter()
+            .any(|tile| tile.col == col && tile.row == row && tile.has_image)
+    }
+
+    // [scale, offset_x, offset_y] the tile's image was last placed with, for restoring editor
+    // controls; None if the tile has no image
+    #[wasm_bindgen]
+    pub fn get_tile_transform(&self, col: u32, row: u32) -> Option<Vec<f32>> {
+        self.tile_info(col, row)
+            .filter(|tile| tile.has_image)
+            .map(|tile| vec![tile.scale, tile.offset_x as f32, tile.offset_y as f32])
+    }
+
+    // Protect a tile from being overwritten: loads, clears, filters and transforms on it fail
+    // until unlock_tile. Grid-wide operations such as clear_all_tiles are not blocked.
+    #[wasm_bindgen]
+    pub fn lock_tile(&mut self, col: u32, row: u32) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_position(col, row)?;
+
+        self.tile_info_mut(col, row).locked = true;
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn unlock_tile(&mut self, col: u32, row: u32) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_position(col, row)?;
+
+        // Entries that only existed to hold the lock are dropped
+        if let Some(tile_info) = self.take_tile_info(col, row) {
+            self.restore_tile_info(TileInfo {
+                locked: false,
+                ..tile_info
+            });
+        }
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn is_tile_locked(&self, col: u32, row: u32) -> bool {
+        self.tile_info(col, row).is_some_and(|tile| tile.locked)
+    }
+
+    // Whether the tile's image has been changed by a filter or transform since it was loaded
+    #[wasm_bindgen]
+    pub fn is_tile_modified(&self, col: u32, row: u32) -> bool {
+        self.loaded_tiles
+            .iter()
+            .any(|tile| tile.col == col && tile.row == row && tile.has_image && tile.modified)
+    }
+
+    #[wasm_bindgen]
+    pub fn get_loaded_tile_count(&self) -> usize {
+        self.loaded_tiles
+            .iter()
+            .filter(|tile| tile.has_image)
+            .count()
+    }
+
+    // Load the image into the first empty cell in row-major order and return its [col, row],
+    // so dropped batches flow into the grid without explicit positions
+    #[wasm_bindgen]
+    pub fn auto_place_image(&mut self, image_data: &[u8], scale: f32) -> Result<Vec<u32>, JsValue> {
+        let cell = self.next_empty_tile().ok_or_else(|| {
+            JsValue::from_str(&format!(
+                "No empty cell left in the {}x{} grid",
+                self.num_cols, self.num_rows
+            ))
+        })?;
+        self.load_image_from_bytes_with_scale(image_data, cell[0], cell[1], scale)?;
+        Ok(cell)
+    }
+
+    // Number of cells without an image
+    #[wasm_bindgen]
+    pub fn empty_tile_count(&self) -> u32 {
+        (self.num_cols * self.num_rows).saturating_sub(self.get_loaded_tile_count() as u32)
+    }
+
+    // [col, row] of the first unlocked cell without an image in row-major order, if any
+    #[wasm_bindgen]
+    pub fn next_empty_tile(&self) -> Option<Vec<u32>> {
+        (0..self.num_rows)
+            .flat_map(|row| (0..self.num_cols).map(move |col| (col, row)))
+            .find(|&(col, row)| !self.is_tile_loaded(col, row) && !self.is_tile_locked(col, row))
+            .map(|(col, row)| vec![col, row])
+    }
+
+    #[wasm_bindgen]
+    pub fn get_loaded_tiles(&self) -> Vec<u32> {
+        // Flat [col0, row0, col1, row1, ...] in load order
+        self.loaded_tiles
+            .iter()
+            .filter(|tile| tile.has_image)
+            .flat_map(|tile| [tile.col, tile.row])
+            .collect()
+    }
+
+    #[wasm_bindgen]
+    pub fn create_proxy_from_bytes(
+        &self,
+        image_data: &[u8],
+        scale: f32,
+    ) -> Result<Vec<u8>, JsValue> {
+        ImageBuffer::validate_scale(scale)?;
+
+        // Decode the image
+        let img = decode_image(image_data)?;
+
+        // Calculate scaled dimensions
+        let scaled_width = (self.tile_width as f32 * scale) as u32;
+        let scaled_height = (self.tile_height as f32 * scale) as u32;
+
+        // Resize with aspect ratio preservation
+        let resized_img = resize_preserve_aspect_ratio(
+            &img,
+            scaled_width,
+            scaled_height,
+            fit_filter(&img, scaled_width, scaled_height),
+        );
+        let rgba_img = to_rgba8_dithered(&resized_img);
+
+        // Return the raw RGBA pixel data
+        Ok(rgba_img.into_raw())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_proxy_dimensions(&self, image_data: &[u8], scale: f32) -> Result<Vec<u32>, JsValue> {
+        ImageBuffer::validate_scale(scale)?;
+
+        // Decode the image to get dimensions
+        let img = decode_image(image_data)?;
+
+        // Calculate scaled dimensions
+        let scaled_width = (self.tile_width as f32 * scale) as u32;
+        let scaled_height = (self.tile_height as f32 * scale) as u32;
+
+        // Get the actual dimensions after aspect ratio preserving resize
+        let resized_img = resize_preserve_aspect_ratio(
+            &img,
+            scaled_width,
+            scaled_height,
+            fit_filter(&img, scaled_width, scaled_height),
+        );
+
+        // Return [width, height]
+        Ok(vec![resized_img.width(), resized_img.height()])
+    }
+
+    #[wasm_bindgen]
+    pub fn get_original_dimensions(&self, image_data: &[u8]) -> Result<Vec<u32>, JsValue> {
+        // Decode the image to get original dimensions
+        let img = decode_image(image_data)?;
+
+        // Get the original dimensions without any scaling or resizing
+        let (original_width, original_height) = img.dimensions();
+
+        // Return [width, height]
+        Ok(vec![original_width, original_height])
+    }
+
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn load_rgba_proxy_with_offset(
+        &mut self,
+        rgba_data: &[u8],
+        proxy_width: u32,
+        proxy_height: u32,
+        col: u32,
+        row: u32,
+        offset_x: i32,
+        offset_y: i32,
+    ) -> Result<(), JsValue> {
+        let params = ProxyLoadParams {
+            proxy_width,
+            proxy_height,
+            col,
+            row,
+            offset_x,
+            offset_y,
+        };
+        self.load_rgba_proxy_internal(rgba_data, params)
+    }
+
+    fn load_rgba_proxy_internal(
+        &mut self,
+        rgba_data: &[u8],
+        params: ProxyLoadParams,
+    ) -> Result<(), JsValue> {
+        // Validate tile position
+        self.validate_tile_unlocked(params.col, params.row)?;
+        let (tile_width, tile_height) = self.tile_size(params.col, params.row);
+
+        // Validate RGBA data length
+        let expected_len = (params.proxy_width * params.proxy_height * 4) as usize;
+        if rgba_data.len() != expected_len {
+            return Err(JsValue::from_str(&format!(
+                "Invalid RGBA data length. Expected {}, got {}",
+                expected_len,
+                rgba_data.len()
+            )));
+        }
+
+        // Remove any existing tile info for this position, then add new one
+        let tile_info = self.mark_tile_loaded(params.col, params.row);
+        tile_info.offset_x = params.offset_x;
+        tile_info.offset_y = params.offset_y;
+
+        // Calculate offsets for positioning the proxy image within the tile
+        let center_x = (tile_width as i32 - params.proxy_width as i32) / 2;
+        let center_y = (tile_height as i32 - params.proxy_height as i32) / 2;
+
+        // Draw the proxy into the tile, filling uncovered areas with background
+        let mut rgba_data = rgba_data.to_vec();
+        self.match_buffer_alpha(&mut rgba_data);
+        self.composite_into_tile(
+            params.col,
+            params.row,
+            &rgba_data,
+            params.proxy_width,
+            params.proxy_height,
+            center_x + params.offset_x,
+            center_y + params.offset_y,
+        );
+
+        Ok(())
+    }
+}
+
+// Current time in milliseconds for measuring durations. In the browser this is
+// performance.now() (0 where there is no window, e.g. in workers); native builds use a
+// monotonic clock, which keeps the timed load testable.
+#[cfg(target_arch = "wasm32")]
+fn now_ms() -> f64 {
+    web_sys::window()
+        .and_then(|window| window.performance())
+        .map_or(0.0, |performance| performance.now())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now_ms() -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    EPOCH.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+}
+
+// Start offset of each column (or row) given their sizes and the gutter between them
+fn layout_offsets(sizes: &[u32], gutter: u32) -> Vec<u32> {
+    let mut offset = 0u32;
+    sizes
+        .iter()
+        .map(|size| {
+            let start = offset;
+            // Saturates only past the last cell, whose start is never used as an extent
+            offset = offset.saturating_add(*size).saturating_add(gutter);
+            start
+        })
+        .collect()
+}
+
+// Total pixel length of cells laid out with gutters between them, or None if it overflows
+fn layout_extent(sizes: &[u32], gutter: u32) -> Option<u32> {
+    let gutters = u32::try_from(sizes.len().saturating_sub(1))
+        .ok()?
+        .checked_mul(gutter)?;
+    sizes
+        .iter()
+        .try_fold(gutters, |total, &size| total.checked_add(size))
+}
+
+// Byte length of an RGBA buffer, or None if it can't be addressed
+fn buffer_len(width: u32, height: u32) -> Option<usize> {
+    (width as usize)
+        .checked_mul(height as usize)?
+        .checked_mul(4)
+}
+
+// Index of the column (or row) covering a pixel coordinate, if it isn't in a gutter
+fn layout_index(offsets: &[u32], sizes: &[u32], position: usize) -> Option<u32> {
+    let index = offsets
+        .partition_point(|&offset| offset as usize <= position)
+        .checked_sub(1)?;
+    if position < (offsets[index] + sizes[index]) as usize {
+        Some(index as u32)
+    } else {
+        None
+    }
+}
+
+// Perceptual luminance of an RGBA pixel using the Rec. 601 weights
+fn luminance(pixel: &[u8]) -> f32 {
+    0.299 * pixel[0] as f32 + 0.587 * pixel[1] as f32 + 0.114 * pixel[2] as f32
+}
+
+// Separable box blur over interleaved pixels, replicating edge pixels past the borders.
+// Each pass keeps a running sum per channel so the cost is independent of the radius.
+fn box_blur(pixels: &mut [u8], width: u32, height: u32, channels: usize, radius: u32) {
+    let width = width as usize;
+    let height = height as usize;
+    // Cap the window so its sum and the priming loop stay bounded
+    let radius = radius.min(width.max(height) as u32) as usize;
+    if width == 0 || height == 0 || radius == 0 {
+        return;
+    }
+
+    let mut line = Vec::new();
+    // Horizontal pass over every row, then vertical pass over every column
+    for (count, stride, line_len, step) in [
+        (height, width * channels, width, channels),
+        (width, channels, height, width * channels),
+    ] {
+        for line_index in 0..count {
+            let base = line_index * stride;
+            line.clear();
+            line.extend((0..line_len).map(|i| base + i * step));
+            blur_line(pixels, &line, channels, radius);
+        }
+    }
+}
+
+// Box blur one row or column given the byte offsets of its pixels
+fn blur_line(pixels: &mut [u8], offsets: &[usize], channels: usize, radius: usize) {
+    let len = offsets.len();
+    let window = (2 * radius + 1) as u32;
+    let source: Vec<u8> = offsets
+        .iter()
+        .flat_map(|&offset| pixels[offset..offset + channels].iter().copied())
+        .collect();
+    let sample = |i: isize, channel: usize| {
+        let clamped = i.clamp(0, len as isize - 1) as usize;
+        source[clamped * channels + channel] as u32
+    };
+
+    for channel in 0..channels {
+        // Prime the window centered on the first pixel
+        let mut sum: u32 = (-(radius as isize)..=radius as isize)
+            .map(|i| sample(i, channel))
+            .sum();
+        for (i, &offset) in offsets.iter().enumerate() {
+            pixels[offset + channel] = ((sum + window / 2) / window) as u8;
+            // Slide the window one pixel to the right
+            let i = i as isize;
+            sum += sample(i + radius as isize + 1, channel);
+            sum -= sample(i - radius as isize, channel);
+        }
+    }
+}
+
+// Pixel ranges along one axis covered by grid lines centered on each tile's start and end edge,
+// clipped to the buffer
+fn grid_line_spans(
+    offsets: &[u32],
+    sizes: &[u32],
+    thickness: u32,
+    limit: u32,
+) -> Vec<Range<usize>> {
+    let mut edges: Vec<u32> = offsets
+        .iter()
+        .zip(sizes)
+        .flat_map(|(&offset, &size)| [offset, offset + size])
+        .collect();
+    edges.sort_unstable();
+    edges.dedup();
+
+    edges
+        .into_iter()
+        .map(|edge| {
+            let start = edge
+                .saturating_sub(thickness / 2)
+                .min(limit.saturating_sub(thickness));
+            start as usize..(start + thickness).min(limit) as usize
+        })
+        .collect()
+}
+
+// 4x4 Bayer matrix of ordered-dither thresholds, in sixteenths
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+// Convert to 8-bit RGBA for compositing. Resizing keeps the source's native bit depth, so
+// high-bit-depth images are ordered-dithered on the way down instead of truncated, which
+// breaks up banding in smooth gradients. Values that are exact 8-bit levels stay unchanged.
+fn to_rgba8_dithered(img: &DynamicImage) -> image::RgbaImage {
+    let color = img.color();
+    if color.bytes_per_pixel() / color.channel_count() == 1 {
+        return img.to_rgba8();
+    }
+
+    let source = img.to_rgba16();
+    image::RgbaImage::from_fn(source.width(), source.height(), |x, y| {
+        let threshold = (BAYER_4X4[y as usize % 4][x as usize % 4] as f32 + 0.5) / 16.0;
+        let [r, g, b, a] = source.get_pixel(x, y).0;
+        let dither = |value: u16| (value as f32 / 257.0 + threshold).floor().min(255.0) as u8;
+        image::Rgba([
+            dither(r),
+            dither(g),
+            dither(b),
+            (a as f32 / 257.0).round() as u8,
+        ])
+    })
+}
+
+// Shift an image right and down by fractions of a pixel (each in [0, 1)), sampling bilinearly
+// between each pixel and its upper-left neighbours. Pixels along the top and left edges clamp
+// to the edge rather than fading out, so the image keeps its size.
+fn shift_subpixel(img: &image::RgbaImage, fraction_x: f32, fraction_y: f32) -> image::RgbaImage {
+    image::RgbaImage::from_fn(img.width(), img.height(), |x, y| {
+        let (x0, y0) = (x.saturating_sub(1), y.saturating_sub(1));
+        let blend = |channel: usize| {
+            let texel = |px: u32, py: u32| img.get_pixel(px, py).0[channel] as f32;
+            let top = texel(x0, y0) * fraction_x + texel(x, y0) * (1.0 - fraction_x);
+            let bottom = texel(x0, y) * fraction_x + texel(x, y) * (1.0 - fraction_x);
+            (top * fraction_y + bottom * (1.0 - fraction_y))
+                .round()
+                .clamp(0.0, 255.0) as u8
+        };
+        image::Rgba([blend(0), blend(1), blend(2), blend(3)])
+    })
+}
+
+// The [x, y, width, height] left after trimming edge rows and columns that all match the
+// top-left pixel within `tolerance` per channel. None when there is nothing to trim or the
+// whole image matches.
+fn uniform_border_crop(img: &DynamicImage, tolerance: u8) -> Option<(u32, u32, u32, u32)> {
+    let rgba = img.to_rgba8();
+    let (width, height) = rgba.dimensions();
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let border = rgba.get_pixel(0, 0).0;
+    let matches = |x: u32, y: u32| {
+        rgba.get_pixel(x, y)
+            .0
+            .iter()
+            .zip(border)
+            .all(|(&channel, reference)| channel.abs_diff(reference) <= tolerance)
+    };
+    let row_uniform = |y: u32| (0..width).all(|x| matches(x, y));
+    let col_uniform = |x: u32, rows: &std::ops::Range<u32>| rows.clone().all(|y| matches(x, y));
+
+    let top = (0..height).find(|&y| !row_uniform(y))?;
+    let bottom = (0..height).rev().find(|&y| !row_uniform(y))? + 1;
+    let rows = top..bottom;
+    let left = (0..width).find(|&x| !col_uniform(x, &rows))?;
+    let right = (0..width).rev().find(|&x| !col_uniform(x, &rows))? + 1;
+
+    if (left, top, right, bottom) == (0, 0, width, height) {
+        return None;
+    }
+    Some((left, top, right - left, bottom - top))
+}
+
+// Multiply an RGBA pixel's color channels by its alpha, rounding to nearest
+fn premultiply_pixel(pixel: &mut [u8]) {
+    let alpha = pixel[3] as u32;
+    for channel in &mut pixel[..3] {
+        *channel = ((*channel as u32 * alpha + 127) / 255) as u8;
+    }
+}
+
+// Multiply a pixel's alpha by `factor` (0 to 1). Premultiplied pixels scale their color
+// channels by the same factor so they never exceed the new alpha.
+fn scale_alpha(pixel: &mut [u8], factor: f32, premultiplied: bool) {
+    let alpha = (pixel[3] as f32 * factor).round() as u8;
+    pixel[3] = alpha;
+    if premultiplied {
+        for channel in &mut pixel[..3] {
+            *channel = (*channel as f32 * factor).round().min(alpha as f32) as u8;
+        }
+    }
+}
+
+// Divide a premultiplied RGBA pixel's color channels back out by its alpha. Fully transparent
+// pixels are left as they are.
+fn unpremultiply_pixel(pixel: &mut [u8]) {
+    let alpha = pixel[3] as u32;
+    if alpha == 0 {
+        return;
+    }
+    for channel in &mut pixel[..3] {
+        *channel = ((*channel as u32 * 255 + alpha / 2) / alpha).min(255) as u8;
+    }
+}
+
+// Largest accepted scale factor, relative to the tile size
+const MAX_SCALE: f32 = 100.0;
+
+// Check that a scale factor is positive, finite and at most MAX_SCALE
+fn check_scale(scale: f32) -> Result<(), String> {
+    if scale.is_finite() && scale > 0.0 && scale <= MAX_SCALE {
+        Ok(())
+    } else {
+        Err(format!(
+            "Scale must be greater than 0 and at most {}, got {}",
+            MAX_SCALE, scale
+        ))
+    }
+}
+
+// Tile an image at its native size across a target area, wrapping at the right and bottom
+fn repeat_to_fill(img: &DynamicImage, target_width: u32, target_height: u32) -> DynamicImage {
+    let source = img.to_rgba8();
+    let (source_width, source_height) = source.dimensions();
+    if source_width == 0 || source_height == 0 {
+        return DynamicImage::new_rgba8(target_width, target_height);
+    }
+    DynamicImage::ImageRgba8(image::RgbaImage::from_fn(
+        target_width,
+        target_height,
+        |x, y| *source.get_pixel(x % source_width, y % source_height),
+    ))
+}
+
+// Smallest [x0, y0, x1, y1) rectangle containing both the optional existing one and `rect`
+fn union_rect(existing: Option<[u32; 4]>, rect: [u32; 4]) -> [u32; 4] {
+    match existing {
+        Some([x0, y0, x1, y1]) => [
+            x0.min(rect[0]),
+            y0.min(rect[1]),
+            x1.max(rect[2]),
+            y1.max(rect[3]),
+        ],
+        None => rect,
+    }
+}
+
+// Color of a background pattern pixel for the given frame
+fn pattern_color(
+    kind: PatternKind,
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    frame: u32,
+) -> [u8; 4] {
+    match kind {
+        PatternKind::Waves => {
+            let r = ((x as f32 + frame as f32 * 0.1).sin() * 127.0 + 128.0) as u8;
+            let g = ((y as f32 + frame as f32 * 0.15).sin() * 127.0 + 128.0) as u8;
+            let b = (((x + y) as f32 + frame as f32 * 0.2).sin() * 127.0 + 128.0) as u8;
+            [r, g, b, 255]
+        }
+        PatternKind::Checkerboard => {
+            const SQUARE: usize = 16;
+            let shift = frame as usize;
+            let dark = ((x + shift) / SQUARE + (y + shift) / SQUARE) % 2 == 1;
+            let value = if dark { 153 } else { 204 };
+            [value, value, value, 255]
+        }
+        PatternKind::Gradient => {
+            // Position along the diagonal in 0..1, drifting with the frame
+            let span = (width + height).max(1) as f32;
+            let t = ((x + y) as f32 / span + frame as f32 * 0.005).fract();
+            let r = (t * 255.0) as u8;
+            let g = ((1.0 - t) * 255.0) as u8;
+            let b = ((0.5 - (t - 0.5).abs()) * 2.0 * 255.0) as u8;
+            [r, g, b, 255]
+        }
+        PatternKind::SolidNoise => {
+            // Cheap integer hash of position and frame
+            let mut hash = (x as u32).wrapping_mul(0x9E37_79B1)
+                ^ (y as u32).wrapping_mul(0x85EB_CA77)
+                ^ frame.wrapping_mul(0xC2B2_AE3D);
+            hash ^= hash >> 15;
+            hash = hash.wrapping_mul(0x2C1B_3C6D);
+            hash ^= hash >> 12;
+            let value = (hash >> 24) as u8;
+            [value, value, value, 255]
+        }
+    }
+}
+
+// Decode image bytes, applying any EXIF orientation so photos load upright
+fn decode_image(image_data: &[u8]) -> Result<DynamicImage, JsValue> {
+    if is_svg(image_data) {
+        return rasterize_svg(image_data, None);
+    }
+    // Animated GIFs always show their first frame
+    if image::guess_format(image_data).ok() == Some(image::ImageFormat::Gif) {
+        return decode_gif_frame(image_data, 0);
+    }
+    // CMYK and YCCK JPEGs (including Adobe's inverted CMYK) come back from the JPEG decoder as
+    // RGB, so print-sourced files need no separate conversion here
+    let img = image::load_from_memory(image_data)
+        .map_err(|e| JsValue::from_str(&format!("Failed to decode image: {}", e)))?;
+
+    Ok(match exif_orientation(image_data) {
+        Some(orientation) => apply_orientation(img, orientation),
+        None => img,
+    })
+}
+
+// Decode one frame of a GIF, composited onto the full logical screen. Indices past the end
+// clamp to the last frame.
+fn decode_gif_frame(image_data: &[u8], frame_index: u32) -> Result<DynamicImage, JsValue> {
+    let decode_error =
+        |e: image::ImageError| JsValue::from_str(&format!("Failed to decode GIF: {}", e));
+    let decoder = GifDecoder::new(std::io::Cursor::new(image_data)).map_err(decode_error)?;
+
+    let mut frame = None;
+    for next in decoder.into_frames().take(frame_index as usize + 1) {
+        frame = Some(next.map_err(decode_error)?);
+    }
+    let frame = frame.ok_or_else(|| JsValue::from_str("GIF has no frames"))?;
+    Ok(DynamicImage::ImageRgba8(frame.into_buffer()))
+}
+
+// Decode image bytes for display within target_width x target_height. Vector input is
+// rasterized straight at the fitted size so it stays crisp; anything else decodes as usual.
+fn decode_image_to_fit(
+    image_data: &[u8],
+    target_width: u32,
+    target_height: u32,
+) -> Result<DynamicImage, JsValue> {
+    if is_svg(image_data) {
+        return rasterize_svg(image_data, Some((target_width, target_height)));
+    }
+    decode_image(image_data)
+}
+
+// Whether the bytes look like an SVG document. Raster formats start with binary signatures,
+// so checking the start of the text for an <svg element is enough.
+fn is_svg(image_data: &[u8]) -> bool {
+    let head = &image_data[..image_data.len().min(1024)];
+    let text = String::from_utf8_lossy(head);
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    (text.starts_with("<?xml") || text.starts_with("<svg") || text.starts_with("<!"))
+        && text.contains("<svg")
+}
+
+// Render an SVG to RGBA, either at its intrinsic size or fitted inside `fit`
+#[cfg(feature = "svg")]
+fn rasterize_svg(image_data: &[u8], fit: Option<(u32, u32)>) -> Result<DynamicImage, JsValue> {
+    use resvg::{tiny_skia, usvg};
+
+    let tree = usvg::Tree::from_data(image_data, &usvg::Options::default())
+        .map_err(|e| JsValue::from_str(&format!("Failed to parse SVG: {}", e)))?;
+    let size = tree.size();
+    let intrinsic = (
+        (size.width().ceil() as u32).max(1),
+        (size.height().ceil() as u32).max(1),
+    );
+    let (width, height) = match fit {
+        Some((target_width, target_height)) => {
+            fit_dimensions(intrinsic.0, intrinsic.1, target_width, target_height)
+        }
+        None => intrinsic,
+    };
+
+    let mut pixmap = tiny_skia::Pixmap::new(width, height)
+        .ok_or_else(|| JsValue::from_str("Failed to allocate SVG canvas"))?;
+    let transform = tiny_skia::Transform::from_scale(
+        width as f32 / size.width(),
+        height as f32 / size.height(),
+    );
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+
+    // tiny-skia stores premultiplied alpha
+    let pixels = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    let img = image::RgbaImage::from_raw(width, height, pixels)
+        .ok_or_else(|| JsValue::from_str("Failed to wrap SVG pixels"))?;
+    Ok(DynamicImage::ImageRgba8(img))
+}
+
+#[cfg(not(feature = "svg"))]
+fn rasterize_svg(_image_data: &[u8], _fit: Option<(u32, u32)>) -> Result<DynamicImage, JsValue> {
+    Err(JsValue::from_str("SVG input requires the svg feature"))
+}
+
+// Decode image bytes so that neither side exceeds max_dim. JPEGs are scaled down during decoding
+// (by 1/2, 1/4 or 1/8), so the full-resolution pixels are never held in memory.
+fn decode_image_downscaled(image_data: &[u8], max_dim: u32) -> Result<DynamicImage, JsValue> {
+    let max_dim = max_dim.max(1);
+    let decode_error =
+        |e: image::ImageError| JsValue::from_str(&format!("Failed to decode image: {}", e));
+
+    let img = if image::guess_format(image_data).ok() == Some(image::ImageFormat::Jpeg) {
+        let mut decoder =
+            JpegDecoder::new(std::io::Cursor::new(image_data)).map_err(decode_error)?;
+        let (width, height) = decoder.dimensions();
+        let longest = width.max(height).max(1) as u64;
+        if longest > max_dim as u64 {
+            let requested = |side: u32| {
+                (side as u64 * max_dim as u64 / longest).clamp(1, u16::MAX as u64) as u16
+            };
+            decoder
+                .scale(requested(width), requested(height))
+                .map_err(decode_error)?;
+        }
+        DynamicImage::from_decoder(decoder).map_err(decode_error)?
+    } else {
+        image::load_from_memory(image_data).map_err(decode_error)?
+    };
+
+    // The JPEG scaler only reaches the nearest power-of-two step at or above the request
+    let img = if img.width() > max_dim || img.height() > max_dim {
+        resize_preserve_aspect_ratio(&img, max_dim, max_dim, ResizeFilter::Triangle)
+    } else {
+        img
+    };
+
+    Ok(match exif_orientation(image_data) {
+        Some(orientation) => apply_orientation(img, orientation),
+        None => img,
+    })
+}
+
+// Find the EXIF orientation tag (1-8) in a JPEG's APP1 segment, if present
+fn exif_orientation(data: &[u8]) -> Option<u16> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+
+    let mut pos = 2;
+    while pos + 4 <= data.len() {
+        if data[pos] != 0xFF {
+            return None;
+        }
+        let marker = data[pos + 1];
+        // Metadata segments all come before the start of scan
+        if marker == 0xDA || marker == 0xD9 {
+            return None;
+        }
+        let length = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let segment = data.get(pos + 4..pos + 2 + length)?;
+        if marker == 0xE1 && segment.starts_with(b"Exif\0\0") {
+            return tiff_orientation(&segment[6..]);
+        }
+        pos += 2 + length;
+    }
+    None
+}
+
+// Read the orientation entry from the first IFD of a TIFF-structured EXIF block
+fn tiff_orientation(tiff: &[u8]) -> Option<u16> {
+    let big_endian = match tiff.get(0..2)? {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None,
+    };
+    let read_u16 = |at: usize| {
+        let bytes = [*tiff.get(at)?, *tiff.get(at + 1)?];
+        Some(if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    };
+    let read_u32 = |at: usize| {
+        let (first, second) = (read_u16(at)? as u32, read_u16(at + 2)? as u32);
+        Some(if big_endian {
+            first << 16 | second
+        } else {
+            second << 16 | first
+        })
+    };
+
+    let ifd_offset = read_u32(4)? as usize;
+    if ifd_offset >= tiff.len() {
+        return None;
+    }
+    let entry_count = read_u16(ifd_offset)? as usize;
+    for entry in 0..entry_count {
+        let entry_offset = ifd_offset + 2 + entry * 12;
+        if read_u16(entry_offset)? == 0x0112 {
+            // Orientation is a SHORT stored at the start of the value field
+            return read_u16(entry_offset + 8).filter(|orientation| (1..=8).contains(orientation));
+        }
+    }
+    None
+}
+
+// Apply the rotation/flip that maps an EXIF orientation back to upright
+fn apply_orientation(img: DynamicImage, orientation: u16) -> DynamicImage {
+    match orientation {
+        2 => img.fliph(),
+        3 => img.rotate180(),
+        4 => img.flipv(),
+        5 => img.rotate90().fliph(),
+        6 => img.rotate90(),
+        7 => img.rotate270().fliph(),
+        8 => img.rotate270(),
+        _ => img,
+    }
+}
+
+// Composite one premultiplied RGBA pixel over another
+fn source_over_premultiplied(src: &[u8], dst: &[u8]) -> [u8; 4] {
+    let inverse_alpha = 255 - src[3] as u32;
+    let mut out = [0; 4];
+    for channel in 0..4 {
+        out[channel] = (src[channel] as u32 + (dst[channel] as u32 * inverse_alpha + 127) / 255)
+            .min(255) as u8;
+    }
+    out
+}
+
+// Composite one RGBA pixel over another with straight (non-premultiplied) alpha
+fn source_over(src: &[u8], dst: &[u8]) -> [u8; 4] {
+    let src_alpha = src[3] as f32 / 255.0;
+    let dst_alpha = dst[3] as f32 / 255.0 * (1.0 - src_alpha);
+    let out_alpha = src_alpha + dst_alpha;
+    if out_alpha <= 0.0 {
+        return [0, 0, 0, 0];
+    }
+
+    let mut out = [0; 4];
+    for channel in 0..3 {
+        let value = (src[channel] as f32 * src_alpha + dst[channel] as f32 * dst_alpha) / out_alpha;
+        out[channel] = value.round() as u8;
+    }
+    out[3] = (out_alpha * 255.0).round() as u8;
+    out
+}
+
+// Sharpen the RGB channels of a packed RGBA image in place with a 3x3 cross kernel: the center
+// weighs 1 + 4 * amount and each edge neighbor -amount. Out-of-bounds neighbors clamp to the edge.
+fn sharpen(pixels: &mut [u8], width: u32, height: u32, amount: f32) {
+    let (width, height) = (width as usize, height as usize);
+    let source = pixels.to_vec();
+    let sample = |x: usize, y: usize, channel: usize| source[(y * width + x) * 4 + channel] as f32;
+
+    for y in 0..height {
+        for x in 0..width {
+            let neighbors = [
+                (x, y.saturating_sub(1)),
+                (x, (y + 1).min(height - 1)),
+                (x.saturating_sub(1), y),
+                ((x + 1).min(width - 1), y),
+            ];
+            for channel in 0..3 {
+                let around: f32 = neighbors
+                    .iter()
+                    .map(|&(nx, ny)| sample(nx, ny, channel))
+                    .sum();
+                let value = sample(x, y, channel) * (1.0 + 4.0 * amount) - around * amount;
+                pixels[(y * width + x) * 4 + channel] = value.round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+}
+
+// Replace the RGB channels of a packed RGBA image with the Sobel gradient magnitude of its
+// luminance, clamped to 255. Out-of-bounds neighbors clamp to the edge.
+fn sobel(pixels: &mut [u8], width: u32, height: u32) {
+    let (width, height) = (width as usize, height as usize);
+    let luma: Vec<f32> = pixels.chunks_exact(4).map(luminance).collect();
+    let sample = |x: usize, y: usize| luma[y * width + x];
+
+    for y in 0..height {
+        let (up, down) = (y.saturating_sub(1), (y + 1).min(height - 1));
+        for x in 0..width {
+            let (left, right) = (x.saturating_sub(1), (x + 1).min(width - 1));
+            let gx = sample(right, up) + 2.0 * sample(right, y) + sample(right, down)
+                - sample(left, up)
+                - 2.0 * sample(left, y)
+                - sample(left, down);
+            let gy = sample(left, down) + 2.0 * sample(x, down) + sample(right, down)
+                - sample(left, up)
+                - 2.0 * sample(x, up)
+                - sample(right, up);
+            let magnitude = (gx * gx + gy * gy).sqrt().round().min(255.0) as u8;
+            pixels[(y * width + x) * 4..(y * width + x) * 4 + 3].fill(magnitude);
+        }
+    }
+}
+
+// Encode a tightly packed RGBA buffer as PNG bytes
+fn encode_png(rgba_data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, JsValue> {
+    let mut png_bytes = Vec::new();
+    PngEncoder::new(&mut png_bytes)
+        .write_image(rgba_data, width, height, image::ColorType::Rgba8)
+        .map_err(|e| JsValue::from_str(&format!("Failed to encode PNG: {}", e)))?;
+    Ok(png_bytes)
+}
+
+fn resize_preserve_aspect_ratio(
+    img: &DynamicImage,
+    target_width: u32,
+    target_height: u32,
+    filter: ResizeFilter,
+) -> DynamicImage {
+    let (original_width, original_height) = img.dimensions();
+    let (new_width, new_height) =
+        fit_dimensions(original_width, original_height, target_width, target_height);
+    img.resize_exact(new_width, new_height, filter.filter_type())
+}
+
+// Enlargement above which the default Lanczos3 resize gives way to Triangle
+const LANCZOS_MAX_ENLARGEMENT: f32 = 4.0;
+
+// Filter for resizes where the caller didn't pick one. Lanczos3 is sharpest, but for big
+// enlargements (say a thumbnail scaled up 8x) it is slow, rings around edges, and has no
+// extra detail to recover, so anything enlarged more than LANCZOS_MAX_ENLARGEMENT times
+// uses the cheaper, smoother Triangle filter instead.
+fn auto_filter(enlargement: f32) -> ResizeFilter {
+    if enlargement > LANCZOS_MAX_ENLARGEMENT {
+        ResizeFilter::Triangle
+    } else {
+        ResizeFilter::Lanczos3
+    }
+}
+
+// auto_filter for fitting an image inside a target, where the tighter axis sets the enlargement
+fn fit_filter(img: &DynamicImage, target_width: u32, target_height: u32) -> ResizeFilter {
+    let enlargement_x = target_width as f32 / img.width().max(1) as f32;
+    let enlargement_y = target_height as f32 / img.height().max(1) as f32;
+    auto_filter(enlargement_x.min(enlargement_y))
+}
+
+// auto_filter for covering or stretching to a target, where the looser axis sets it
+fn cover_filter(img: &DynamicImage, target_width: u32, target_height: u32) -> ResizeFilter {
+    let enlargement_x = target_width as f32 / img.width().max(1) as f32;
+    let enlargement_y = target_height as f32 / img.height().max(1) as f32;
+    auto_filter(enlargement_x.max(enlargement_y))
+}
+
+// Largest size with the original's aspect ratio that fits the target. The tighter axis matches
+// the target exactly and the other is rounded to nearest; integer math avoids float error
+// leaving a one-pixel seam. An empty original has no aspect ratio and maps to 1x1.
+fn fit_dimensions(
+    original_width: u32,
+    original_height: u32,
+    target_width: u32,
+    target_height: u32,
+) -> (u32, u32) {
+    if original_width == 0 || original_height == 0 {
+        return (1, 1);
+    }
+    let (original_width, original_height) = (original_width as u64, original_height as u64);
+    let (target_width, target_height) = (target_width as u64, target_height as u64);
+
+    let (new_width, new_height) = if target_width * original_height
+        <= target_height * original_width
+    {
+        let height = (original_height * target_width * 2 + original_width) / (original_width * 2);
+        (target_width, height)
+    } else {
+        let width = (original_width * target_height * 2 + original_height) / (original_height * 2);
+        (width, target_height)
+    };
+    (new_width.max(1) as u32, new_height.max(1) as u32)
+}
+
+fn resize_to_cover(
+    img: &DynamicImage,
+    target_width: u32,
+    target_height: u32,
+    filter: ResizeFilter,
+) -> DynamicImage {
+    let (original_width, original_height) = img.dimensions();
+
+    // Calculate scaling factor so the image covers both target dimensions
+    let scale_x = target_width as f32 / original_width as f32;
+    let scale_y = target_height as f32 / original_height as f32;
+    let scale = scale_x.max(scale_y);
+
+    // Round and clamp so float error can never leave the covered area a pixel short
+    let new_width = ((original_width as f32 * scale).round() as u32).max(target_width);
+    let new_height = ((original_height as f32 * scale).round() as u32).max(target_height);
+
+    img.resize_exact(new_width, new_height, filter.filter_type())
+}
+
+fn resize_to_width(img: &DynamicImage, target_width: u32, filter: ResizeFilter) -> DynamicImage {
+    let (original_width, original_height) = img.dimensions();
+
+    // Only the width is constrained; the height keeps the aspect ratio
+    let scale = target_width as f32 / original_width as f32;
+    let new_height = ((original_height as f32 * scale).round() as u32).max(1);
+
+    img.resize_exact(target_width, new_height, filter.filter_type())
+}
+
+fn resize_to_height(img: &DynamicImage, target_height: u32, filter: ResizeFilter) -> DynamicImage {
+    let (original_width, original_height) = img.dimensions();
+
+    // Only the height is constrained; the width keeps the aspect ratio
+    let scale = target_height as f32 / original_height as f32;
+    let new_width = ((original_width as f32 * scale).round() as u32).max(1);
+
+    img.resize_exact(new_width, target_height, filter.filter_type())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    // Encode a single-color image as PNG bytes for feeding the load functions
+    fn solid_png(width: u32, height: u32, color: [u8; 4]) -> Vec<u8> {
+        let source = image::RgbaImage::from_pixel(width, height, image::Rgba(color));
+        encode_png(source.as_raw(), width, height).unwrap()
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_image_buffer_creation() {
+        let buffer = ImageBuffer::new(100, 100, 2, 2).unwrap();
+        assert_eq!(buffer.width(), 200);
+        assert_eq!(buffer.height(), 200);
+        assert_eq!(buffer.tile_width(), 100);
+        assert_eq!(buffer.tile_height(), 100);
+        assert_eq!(buffer.data_len(), 200 * 200 * 4);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_pattern_generation() {
+        let mut buffer = ImageBuffer::new(10, 10, 1, 1).unwrap();
+        buffer.generate_pattern(0);
+
+        // Check that data has been populated (not all zeros)
+        let data_ptr = buffer.data_ptr();
+        let data_len = buffer.data_len();
+
+        // This is a basic test - in practice we'd check specific pattern values
+        assert!(data_len > 0);
+        assert!(!data_ptr.is_null());
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_resize_preserve_aspect_ratio() {
+        // Create a simple 2x1 test image (landscape)
+        let img = DynamicImage::new_rgb8(200, 100);
+        let resized = resize_preserve_aspect_ratio(&img, 100, 100, ResizeFilter::Lanczos3);
+
+        // Should fit within 100x100, maintaining aspect ratio
+        let (w, h) = resized.dimensions();
+        assert_eq!(w, 100); // Full width used
+        assert_eq!(h, 50); // Height scaled proportionally
+    }
+
+    #[test]
+    fn test_image_buffer_dimensions() {
+        let buffer = ImageBuffer::new(50, 75, 3, 4).unwrap();
+        assert_eq!(buffer.width, 150); // 50 * 3
+        assert_eq!(buffer.height, 300); // 75 * 4
+        assert_eq!(buffer.tile_width, 50);
+        assert_eq!(buffer.tile_height, 75);
+        assert_eq!(buffer.num_cols, 3);
+        assert_eq!(buffer.num_rows, 4);
+    }
+
+    #[test]
+    fn test_export_to_png_round_trip() {
+        let mut buffer = ImageBuffer::new(20, 10, 2, 1).unwrap();
+        buffer.set_background_color(10, 20, 30, 128);
+        buffer.fill_background();
+
+        let png_bytes = buffer.export_to_png().unwrap();
+        let decoded = image::load_from_memory(&png_bytes).unwrap().to_rgba8();
+        assert_eq!(decoded.dimensions(), (40, 10));
+        assert_eq!(decoded.get_pixel(39, 9).0, [10, 20, 30, 128]);
+
+        // An untouched buffer is all-transparent but must still encode
+        let empty = ImageBuffer::new(8, 8, 1, 1).unwrap();
+        let png_bytes = empty.export_to_png().unwrap();
+        let decoded = image::load_from_memory(&png_bytes).unwrap();
+        assert_eq!(decoded.dimensions(), (8, 8));
+    }
+
+    #[test]
+    fn test_export_tile_to_png() {
+        let mut buffer = ImageBuffer::new(6, 4, 2, 2).unwrap();
+        buffer.set_background_color(0, 0, 255, 255);
+        buffer.fill_background();
+
+        let png_bytes = buffer.export_tile_to_png(1, 1).unwrap();
+        let decoded = image::load_from_memory(&png_bytes).unwrap().to_rgba8();
+        assert_eq!(decoded.dimensions(), (6, 4));
+        assert!(decoded.pixels().all(|p| p.0 == [0, 0, 255, 255]));
+    }
+
+    #[test]
+    fn test_export_to_jpeg_flattens_transparency() {
+        // A fresh buffer is fully transparent, so the export should show the background
+        let mut buffer = ImageBuffer::new(16, 16, 1, 1).unwrap();
+        buffer.set_background_color(200, 100, 50, 0);
+
+        let jpeg_bytes = buffer.export_to_jpeg(95).unwrap();
+        let decoded = image::load_from_memory(&jpeg_bytes).unwrap().to_rgb8();
+        assert_eq!(decoded.dimensions(), (16, 16));
+
+        let pixel = decoded.get_pixel(8, 8).0;
+        for (actual, expected) in pixel.iter().zip([200u8, 100, 50]) {
+            assert!((*actual as i32 - expected as i32).abs() <= 8);
+        }
+
+        // Out-of-range quality is clamped rather than rejected
+        assert!(buffer.export_to_jpeg(0).is_ok());
+        assert!(buffer.export_to_jpeg(255).is_ok());
+    }
+
+    #[test]
+    fn test_fill_mode_covers_tile() {
+        let png_bytes = solid_png(300, 100, [0, 255, 0, 255]);
+
+        let mut buffer = ImageBuffer::new(50, 50, 1, 1).unwrap();
+        buffer
+            .load_image_from_bytes_with_mode(&png_bytes, 0, 0, ScaleMode::Fill)
+            .unwrap();
+        assert!(buffer.is_tile_loaded(0, 0));
+        assert!(buffer.data.chunks_exact(4).all(|p| p == [0, 255, 0, 255]));
+
+        // Fit leaves background bars above and below the wide image
+        buffer
+            .load_image_from_bytes_with_mode(&png_bytes, 0, 0, ScaleMode::Fit)
+            .unwrap();
+        assert_eq!(&buffer.data[0..4], &[255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_stretch_mode_fills_tile_exactly() {
+        let png_bytes = solid_png(200, 100, [255, 0, 0, 255]);
+
+        let mut buffer = ImageBuffer::new(100, 100, 1, 1).unwrap();
+        buffer
+            .load_image_from_bytes_with_mode(&png_bytes, 0, 0, ScaleMode::Stretch)
+            .unwrap();
+        assert!(buffer.data.chunks_exact(4).all(|p| p == [255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_rescale_and_reoffset_use_retained_source() {
+        let png_bytes = solid_png(40, 40, [0, 0, 255, 255]);
+
+        let mut buffer = ImageBuffer::new(40, 40, 1, 1).unwrap();
+        buffer
+            .load_image_from_bytes_with_scale_and_offset(&png_bytes, 0, 0, 1.0, 0, 0)
+            .unwrap();
+        assert_eq!(&buffer.data[0..4], &[0, 0, 255, 255]);
+
+        // Half scale leaves a background border around the centered image
+        buffer.rescale_tile(0, 0, 0.5).unwrap();
+        assert_eq!(&buffer.data[0..4], &[255, 255, 255, 255]);
+        let center = (20 * 40 + 20) * 4;
+        assert_eq!(&buffer.data[center..center + 4], &[0, 0, 255, 255]);
+
+        // Shifting the half-size image to the top-left keeps the scale
+        buffer.reoffset_tile(0, 0, -10, -10).unwrap();
+        assert_eq!(&buffer.data[0..4], &[0, 0, 255, 255]);
+        assert_eq!(&buffer.data[center..center + 4], &[255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_loaded_tile_listing() {
+        let png_bytes = solid_png(4, 4, [0, 0, 0, 255]);
+
+        let mut buffer = ImageBuffer::new(4, 4, 3, 2).unwrap();
+        buffer.load_image_from_bytes(&png_bytes, 2, 1).unwrap();
+        buffer.load_image_from_bytes(&png_bytes, 0, 1).unwrap();
+        buffer.load_image_from_bytes(&png_bytes, 1, 0).unwrap();
+        buffer.clear_tile(0, 1).unwrap();
+
+        assert_eq!(buffer.get_loaded_tile_count(), 2);
+        assert_eq!(buffer.get_loaded_tiles(), vec![2, 1, 1, 0]);
+    }
+
+    #[test]
+    fn test_clear_all_tiles() {
+        let png_bytes = solid_png(4, 4, [0, 0, 0, 255]);
+
+        let mut buffer = ImageBuffer::new(4, 4, 2, 2).unwrap();
+        buffer.load_image_from_bytes(&png_bytes, 0, 0).unwrap();
+        buffer.load_image_from_bytes(&png_bytes, 1, 1).unwrap();
+        buffer.set_background_color(1, 2, 3, 4);
+        buffer.clear_all_tiles();
+
+        assert_eq!(buffer.get_loaded_tile_count(), 0);
+        assert!(!buffer.is_tile_loaded(1, 1));
+        assert!(buffer.data.chunks_exact(4).all(|p| p == [1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn test_flip_tile_twice_restores_pixels() {
+        let mut buffer = ImageBuffer::new(5, 3, 2, 2).unwrap();
+        for (i, byte) in buffer.data.iter_mut().enumerate() {
+            *byte = (i % 251) as u8;
+        }
+        let original = buffer.data.clone();
+
+        buffer.flip_tile_horizontal(1, 0).unwrap();
+        assert_ne!(buffer.data, original);
+        buffer.flip_tile_horizontal(1, 0).unwrap();
+        assert_eq!(buffer.data, original);
+
+        buffer.flip_tile_vertical(0, 1).unwrap();
+        assert_ne!(buffer.data, original);
+        buffer.flip_tile_vertical(0, 1).unwrap();
+        assert_eq!(buffer.data, original);
+    }
+
+    #[test]
+    fn test_rotate_tile() {
+        let mut buffer = ImageBuffer::new(2, 2, 1, 1).unwrap();
+        buffer.data = vec![
+            1, 1, 1, 255, 2, 2, 2, 255, // top row
+            3, 3, 3, 255, 4, 4, 4, 255, // bottom row
+        ];
+        let original = buffer.data.clone();
+
+        buffer.rotate_tile(0, 0, 4).unwrap();
+        assert_eq!(buffer.data, original);
+
+        // Clockwise: the bottom-left pixel moves to the top-left
+        buffer.rotate_tile(0, 0, 1).unwrap();
+        assert_eq!(&buffer.data[0..4], &[3, 3, 3, 255]);
+        assert_eq!(&buffer.data[4..8], &[1, 1, 1, 255]);
+
+        buffer.rotate_tile(0, 0, 3).unwrap();
+        assert_eq!(buffer.data, original);
+    }
+
+    #[test]
+    fn test_rotate_rectangular_tile_refits() {
+        let png_bytes = solid_png(40, 20, [0, 0, 0, 255]);
+
+        let mut buffer = ImageBuffer::new(40, 20, 1, 1).unwrap();
+        buffer.load_image_from_bytes(&png_bytes, 0, 0).unwrap();
+        buffer.rotate_tile(0, 0, 1).unwrap();
+
+        // The 20x40 rotated content shrinks to 10x20 centered in the tile
+        assert_eq!(&buffer.data[0..4], &[255, 255, 255, 255]);
+        let center = (10 * 40 + 20) * 4;
+        assert_eq!(&buffer.data[center..center + 4], &[0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_tile_background_override() {
+        let png_bytes = solid_png(10, 5, [0, 0, 0, 255]);
+
+        let mut buffer = ImageBuffer::new(10, 10, 2, 1).unwrap();
+        buffer.set_tile_background_color(1, 0, 0, 0, 0, 0).unwrap();
+        buffer.load_image_from_bytes(&png_bytes, 0, 0).unwrap();
+        buffer.load_image_from_bytes(&png_bytes, 1, 0).unwrap();
+
+        // Letterbox area below the image uses each tile's own background
+        let below = |col: usize| (9 * 20 + col * 10) * 4;
+        assert_eq!(&buffer.data[below(0)..below(0) + 4], &[255, 255, 255, 255]);
+        assert_eq!(&buffer.data[below(1)..below(1) + 4], &[0, 0, 0, 0]);
+
+        // Clearing keeps the override
+        buffer.clear_tile(1, 0).unwrap();
+        assert!(!buffer.is_tile_loaded(1, 0));
+        assert!(buffer.read_tile_region(1, 0).iter().all(|&b| b == 0));
+        buffer.fill_background();
+        assert!(buffer.read_tile_region(1, 0).iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_tile_border_reapplied_on_load() {
+        let png_bytes = solid_png(10, 10, [0, 0, 255, 255]);
+
+        let mut buffer = ImageBuffer::new(10, 10, 2, 1).unwrap();
+        buffer.set_tile_border(0, 0, 2, 255, 0, 0, 255).unwrap();
+        buffer.load_image_from_bytes(&png_bytes, 0, 0).unwrap();
+        buffer.load_image_from_bytes(&png_bytes, 1, 0).unwrap();
+
+        let tile = buffer.read_tile_region(0, 0);
+        let pixel = |x: usize, y: usize| &tile[(y * 10 + x) * 4..(y * 10 + x) * 4 + 4];
+        assert_eq!(pixel(1, 5), &[255, 0, 0, 255]);
+        assert_eq!(pixel(8, 9), &[255, 0, 0, 255]);
+        assert_eq!(pixel(2, 2), &[0, 0, 255, 255]);
+
+        // The frame never bleeds into the neighboring tile
+        assert!(buffer
+            .read_tile_region(1, 0)
+            .chunks_exact(4)
+            .all(|p| p == [0, 0, 255, 255]));
+    }
+
+    #[test]
+    fn test_grayscale_tile() {
+        let mut buffer = ImageBuffer::new(2, 2, 2, 1).unwrap();
+        buffer.set_background_color(255, 0, 0, 200);
+        buffer.fill_background();
+        buffer.grayscale_tile(0, 0).unwrap();
+
+        // 0.299 * 255 rounds to 76, alpha untouched, other tile untouched
+        assert!(buffer
+            .read_tile_region(0, 0)
+            .chunks_exact(4)
+            .all(|p| p == [76, 76, 76, 200]));
+        assert!(buffer
+            .read_tile_region(1, 0)
+            .chunks_exact(4)
+            .all(|p| p == [255, 0, 0, 200]));
+    }
+
+    #[test]
+    fn test_adjust_tile() {
+        let mut buffer = ImageBuffer::new(2, 1, 1, 1).unwrap();
+        buffer.data = vec![0, 100, 255, 255, 30, 128, 220, 40];
+        let original = buffer.data.clone();
+
+        buffer.adjust_tile(0, 0, 0.0, 1.0).unwrap();
+        assert_eq!(buffer.data, original);
+
+        // Large adjustments saturate instead of wrapping
+        buffer.adjust_tile(0, 0, 0.5, 3.0).unwrap();
+        assert_eq!(buffer.data, vec![0, 172, 255, 255, 0, 255, 255, 40]);
+    }
+
+    #[test]
+    fn test_blur_tile_stays_within_tile() {
+        let mut buffer = ImageBuffer::new(5, 5, 2, 1).unwrap();
+        buffer.set_background_color(0, 0, 0, 255);
+        buffer.fill_background();
+        // Single white pixel in the middle of the left tile
+        let center = (2 * 10 + 2) * 4;
+        buffer.data[center..center + 3].copy_from_slice(&[255, 255, 255]);
+        let right_tile = buffer.read_tile_region(1, 0);
+
+        buffer.blur_tile(0, 0, 0).unwrap();
+        assert_eq!(buffer.data[center], 255);
+
+        buffer.blur_tile(0, 0, 1).unwrap();
+        // 255 spread over a 3x3 window
+        assert_eq!(buffer.data[center], 28);
+        assert_eq!(buffer.data[center - 4], 28);
+        assert_eq!(buffer.data[center + 3], 255);
+        assert_eq!(buffer.read_tile_region(1, 0), right_tile);
+
+        // A huge radius is clamped to the tile rather than looping over it
+        buffer.blur_tile(0, 0, u32::MAX).unwrap();
+        assert_eq!(buffer.read_tile_region(1, 0), right_tile);
+    }
+
+    #[test]
+    fn test_copy_tile() {
+        let png_bytes = solid_png(4, 4, [9, 8, 7, 255]);
+
+        let mut buffer = ImageBuffer::new(4, 4, 2, 1).unwrap();
+        buffer.load_image_from_bytes(&png_bytes, 0, 0).unwrap();
+        buffer.copy_tile(0, 0, 0, 0).unwrap();
+        buffer.copy_tile(0, 0, 1, 0).unwrap();
+
+        assert!(buffer.is_tile_loaded(0, 0));
+        assert!(buffer.is_tile_loaded(1, 0));
+        assert_eq!(buffer.read_tile_region(1, 0), buffer.read_tile_region(0, 0));
+        assert!(buffer.data.chunks_exact(4).all(|p| p == [9, 8, 7, 255]));
+    }
+
+    #[test]
+    fn test_swap_tiles_moves_loaded_state() {
+        let png_bytes = solid_png(4, 4, [9, 8, 7, 255]);
+
+        let mut buffer = ImageBuffer::new(4, 4, 2, 1).unwrap();
+        buffer.fill_background();
+        buffer.load_image_from_bytes(&png_bytes, 0, 0).unwrap();
+        let loaded_pixels = buffer.read_tile_region(0, 0);
+        let empty_pixels = buffer.read_tile_region(1, 0);
+
+        buffer.swap_tiles(0, 0, 1, 0).unwrap();
+        assert!(!buffer.is_tile_loaded(0, 0));
+        assert!(buffer.is_tile_loaded(1, 0));
+        assert_eq!(buffer.read_tile_region(0, 0), empty_pixels);
+        assert_eq!(buffer.read_tile_region(1, 0), loaded_pixels);
+
+        buffer.swap_tiles(1, 0, 1, 0).unwrap();
+        assert!(buffer.is_tile_loaded(1, 0));
+    }
+
+    #[test]
+    fn test_move_tile_clears_origin() {
+        let png_bytes = solid_png(4, 4, [9, 8, 7, 255]);
+
+        let mut buffer = ImageBuffer::new(4, 4, 2, 1).unwrap();
+        buffer.load_image_from_bytes(&png_bytes, 0, 0).unwrap();
+        let loaded_pixels = buffer.read_tile_region(0, 0);
+
+        buffer.move_tile(0, 0, 1, 0).unwrap();
+        assert!(!buffer.is_tile_loaded(0, 0));
+        assert!(buffer.is_tile_loaded(1, 0));
+        assert_eq!(buffer.read_tile_region(1, 0), loaded_pixels);
+        assert!(buffer
+            .read_tile_region(0, 0)
+            .chunks_exact(4)
+            .all(|p| p == [255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn test_gutter_layout() {
+        let png_bytes = solid_png(10, 10, [0, 0, 0, 255]);
+
+        let mut buffer = ImageBuffer::with_gutter(10, 10, 3, 2, 2).unwrap();
+        assert_eq!(buffer.width(), 34);
+        assert_eq!(buffer.height(), 22);
+        assert_eq!(buffer.data_len(), 34 * 22 * 4);
+
+        buffer.load_image_from_bytes(&png_bytes, 1, 1).unwrap();
+        let pixel = |buffer: &ImageBuffer, x: usize, y: usize| {
+            let index = (y * 34 + x) * 4;
+            buffer.data[index..index + 4].to_vec()
+        };
+        // Tile (1, 1) starts after one tile plus one gutter in each direction
+        assert_eq!(pixel(&buffer, 12, 12), vec![0, 0, 0, 255]);
+        assert_eq!(pixel(&buffer, 21, 21), vec![0, 0, 0, 255]);
+        // The gutters around it keep the background
+        assert_eq!(pixel(&buffer, 11, 12), vec![255, 255, 255, 255]);
+        assert_eq!(pixel(&buffer, 22, 12), vec![255, 255, 255, 255]);
+        assert_eq!(pixel(&buffer, 12, 11), vec![255, 255, 255, 255]);
+
+        // Gutter zero matches the plain constructor
+        let plain = ImageBuffer::with_gutter(10, 10, 3, 2, 0).unwrap();
+        assert_eq!(plain.data, ImageBuffer::new(10, 10, 3, 2).unwrap().data);
+    }
+
+    #[test]
+    fn test_nonuniform_layout() {
+        let png_bytes = solid_png(8, 8, [0, 0, 0, 255]);
+
+        let mut buffer = ImageBuffer::new_nonuniform(vec![10, 30], vec![5, 15]).unwrap();
+        assert_eq!(buffer.width(), 40);
+        assert_eq!(buffer.height(), 20);
+        assert_eq!(buffer.column_width(1), Some(30));
+        assert_eq!(buffer.row_height(1), Some(15));
+        assert_eq!(buffer.column_width(2), None);
+        assert_eq!(buffer.tile_at_pixel(12, 4), Some((1, 0)));
+        assert_eq!(buffer.tile_at_pixel(9, 5), Some((0, 1)));
+
+        // Stretch fills exactly the 30x15 cell, which starts at (10, 5)
+        buffer
+            .load_image_from_bytes_with_mode(&png_bytes, 1, 1, ScaleMode::Stretch)
+            .unwrap();
+        let tile = buffer.read_tile_region(1, 1);
+        assert_eq!(tile.len(), 30 * 15 * 4);
+        assert!(tile.chunks_exact(4).all(|p| p == [0, 0, 0, 255]));
+        assert_eq!(
+            &buffer.data[(5 * 40 + 9) * 4..(5 * 40 + 10) * 4],
+            &[0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_get_and_set_pixel() {
+        let mut buffer = ImageBuffer::new(3, 3, 2, 1).unwrap();
+        buffer.set_pixel(5, 2, 1, 2, 3, 4);
+        assert_eq!(buffer.get_pixel(5, 2), Some(vec![1, 2, 3, 4]));
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![0, 0, 0, 0]));
+
+        // Out of bounds reads return None and writes do nothing
+        let before = buffer.data.clone();
+        buffer.set_pixel(6, 0, 9, 9, 9, 9);
+        buffer.set_pixel(0, 3, 9, 9, 9, 9);
+        assert_eq!(buffer.data, before);
+        assert_eq!(buffer.get_pixel(6, 0), None);
+    }
+
+    #[test]
+    fn test_tile_at_with_gutter() {
+        let buffer = ImageBuffer::with_gutter(10, 10, 2, 2, 3).unwrap();
+        assert_eq!(buffer.tile_at(0, 0), Some(vec![0, 0]));
+        assert_eq!(buffer.tile_at(9, 12), None);
+        assert_eq!(buffer.tile_at(13, 13), Some(vec![1, 1]));
+        assert_eq!(buffer.tile_at(22, 22), Some(vec![1, 1]));
+        assert_eq!(buffer.tile_at(23, 0), None);
+    }
+
+    #[test]
+    fn test_fill_tile_with_color_survives_pattern() {
+        let mut buffer = ImageBuffer::new(4, 4, 2, 1).unwrap();
+        buffer.fill_tile_with_color(1, 0, 10, 20, 30, 255).unwrap();
+        assert!(buffer.is_tile_loaded(1, 0));
+
+        buffer.generate_pattern(3);
+        assert!(buffer
+            .read_tile_region(1, 0)
+            .chunks_exact(4)
+            .all(|p| p == [10, 20, 30, 255]));
+    }
+
+    #[test]
+    fn test_set_tile_opacity() {
+        let png_bytes = solid_png(4, 4, [50, 60, 70, 255]);
+
+        let mut buffer = ImageBuffer::new(4, 4, 1, 1).unwrap();
+        buffer.load_image_from_bytes(&png_bytes, 0, 0).unwrap();
+        buffer.set_tile_opacity(0, 0, 0.5).unwrap();
+        assert!(buffer
+            .data
+            .chunks_exact(4)
+            .all(|p| p[..3] == [50, 60, 70] && (127..=128).contains(&p[3])));
+
+        // Reloading brings back full opacity
+        buffer.load_image_from_bytes(&png_bytes, 0, 0).unwrap();
+        assert!(buffer.data.chunks_exact(4).all(|p| p[3] == 255));
+        assert_eq!(buffer.tile_info(0, 0).unwrap().opacity, 1.0);
+    }
+
+    #[test]
+    fn test_load_image_from_rgba() {
+        let pixels = [200, 100, 0, 255].repeat(8 * 4);
+
+        let mut buffer = ImageBuffer::new(8, 8, 1, 1).unwrap();
+        buffer
+            .load_image_from_rgba(&pixels, 8, 4, 0, 0, 1.0)
+            .unwrap();
+        assert!(buffer.is_tile_loaded(0, 0));
+        assert_eq!(&buffer.data[0..4], &[200, 100, 0, 255]);
+        // Rows past the 8x4 image fall back to background
+        assert_eq!(buffer.get_pixel(0, 7), Some(vec![255, 255, 255, 255]));
+
+        // A single pixel still loads and scales up to the tile
+        let mut buffer = ImageBuffer::new(4, 4, 1, 1).unwrap();
+        buffer
+            .load_image_from_rgba(&[10, 20, 30, 255], 1, 1, 0, 0, 1.0)
+            .unwrap();
+        assert!(buffer.is_tile_loaded(0, 0));
+        assert_eq!(buffer.get_pixel(3, 3), Some(vec![10, 20, 30, 255]));
+    }
+
+    #[test]
+    fn test_fit_dimensions_with_empty_original() {
+        assert_eq!(fit_dimensions(0, 0, 16, 8), (1, 1));
+        assert_eq!(fit_dimensions(0, 5, 16, 8), (1, 1));
+        assert_eq!(fit_dimensions(5, 0, 16, 8), (1, 1));
+        assert_eq!(fit_dimensions(4, 2, 16, 16), (16, 8));
+    }
+
+    // Splice an APP1 EXIF segment carrying only an orientation tag right after the SOI marker
+    fn with_exif_orientation(jpeg: &[u8], orientation: u16) -> Vec<u8> {
+        let mut tiff = b"MM\0\x2a\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01".to_vec();
+        tiff.extend_from_slice(&orientation.to_be_bytes());
+        tiff.extend_from_slice(&[0; 6]);
+
+        let mut segment = b"Exif\0\0".to_vec();
+        segment.extend_from_slice(&tiff);
+
+        let mut out = jpeg[..2].to_vec();
+        out.extend_from_slice(&[0xFF, 0xE1]);
+        out.extend_from_slice(&(segment.len() as u16 + 2).to_be_bytes());
+        out.extend_from_slice(&segment);
+        out.extend_from_slice(&jpeg[2..]);
+        out
+    }
+
+    #[test]
+    fn test_exif_orientation_applied_on_decode() {
+        let mut buffer = ImageBuffer::new(16, 8, 1, 1).unwrap();
+        buffer.fill_tile_with_color(0, 0, 10, 20, 30, 255).unwrap();
+        let jpeg = buffer.export_to_jpeg(90).unwrap();
+
+        assert_eq!(exif_orientation(&jpeg), None);
+        assert_eq!(buffer.get_original_dimensions(&jpeg).unwrap(), vec![16, 8]);
+
+        let rotated = with_exif_orientation(&jpeg, 6);
+        assert_eq!(exif_orientation(&rotated), Some(6));
+        assert_eq!(
+            buffer.get_original_dimensions(&rotated).unwrap(),
+            vec![8, 16]
+        );
+    }
+
+    #[test]
+    fn test_apply_orientation_covers_all_values() {
+        // 2x1 source: a@(0,0) b@(1,0)
+        let a = image::Rgba([1, 0, 0, 255]);
+        let b = image::Rgba([2, 0, 0, 255]);
+        let src =
+            DynamicImage::ImageRgba8(image::RgbaImage::from_fn(
+                2,
+                1,
+                |x, _| {
+                    if x == 0 {
+                        a
+                    } else {
+                        b
+                    }
+                },
+            ));
+
+        let expected = [
+            (1, (2, 1), [a, b]),
+            (2, (2, 1), [b, a]),
+            (3, (2, 1), [b, a]),
+            (4, (2, 1), [a, b]),
+            (5, (1, 2), [a, b]),
+            (6, (1, 2), [a, b]),
+            (7, (1, 2), [b, a]),
+            (8, (1, 2), [b, a]),
+        ];
+        for (orientation, dimensions, pixels) in expected {
+            let oriented = apply_orientation(src.clone(), orientation).to_rgba8();
+            assert_eq!(
+                oriented.dimensions(),
+                dimensions,
+                "orientation {}",
+                orientation
+            );
+            let actual: Vec<_> = oriented.pixels().copied().collect();
+            assert_eq!(actual, pixels, "orientation {}", orientation);
+        }
+    }
+
+    #[test]
+    fn test_generate_pattern_kind_skips_loaded_tiles() {
+        let kinds = [
+            PatternKind::Waves,
+            PatternKind::Checkerboard,
+            PatternKind::Gradient,
+            PatternKind::SolidNoise,
+        ];
+        for kind in kinds {
+            let mut buffer = ImageBuffer::new(4, 4, 2, 1).unwrap();
+            buffer.fill_tile_with_color(0, 0, 1, 2, 3, 255).unwrap();
+            buffer.generate_pattern_kind(7, kind);
+
+            assert_eq!(
+                buffer.get_pixel(2, 2),
+                Some(vec![1, 2, 3, 255]),
+                "{:?}",
+                kind
+            );
+            assert_eq!(buffer.get_pixel(5, 2).unwrap()[3], 255, "{:?}", kind);
+        }
+
+        // The default pattern is the wave variant
+        let mut waves = ImageBuffer::new(4, 4, 1, 1).unwrap();
+        let mut default = ImageBuffer::new(4, 4, 1, 1).unwrap();
+        waves.generate_pattern_kind(3, PatternKind::Waves);
+        default.generate_pattern(3);
+        assert_eq!(waves.data, default.data);
+    }
+
+    #[test]
+    fn test_take_dirty_rect() {
+        let mut buffer = ImageBuffer::new(4, 4, 3, 2).unwrap();
+        assert_eq!(buffer.take_dirty_rect(), None);
+
+        buffer.fill_tile_with_color(1, 0, 1, 2, 3, 255).unwrap();
+        buffer.grayscale_tile(2, 1).unwrap();
+        assert_eq!(buffer.take_dirty_rect(), Some(vec![4, 0, 8, 8]));
+        assert_eq!(buffer.take_dirty_rect(), None);
+
+        buffer.set_pixel(1, 6, 0, 0, 0, 255);
+        assert_eq!(buffer.take_dirty_rect(), Some(vec![1, 6, 1, 1]));
+
+        buffer.generate_pattern(0);
+        assert_eq!(buffer.take_dirty_rect(), Some(vec![0, 0, 12, 8]));
+
+        buffer.flip_tile_horizontal(1, 1).unwrap();
+        assert_eq!(buffer.take_dirty_rect(), Some(vec![4, 4, 4, 4]));
+        buffer.flip_tile_vertical(0, 0).unwrap();
+        assert_eq!(buffer.take_dirty_rect(), Some(vec![0, 0, 4, 4]));
+    }
+
+    #[test]
+    fn test_resize_grid() {
+        let mut buffer = ImageBuffer::new(4, 4, 2, 1).unwrap();
+        buffer.fill_tile_with_color(0, 0, 10, 0, 0, 255).unwrap();
+        buffer.fill_tile_with_color(1, 0, 0, 10, 0, 255).unwrap();
+
+        buffer.resize_grid(3, 2).unwrap();
+        assert_eq!((buffer.width(), buffer.height()), (12, 8));
+        assert_eq!(buffer.data_len(), 12 * 8 * 4);
+        assert_eq!(buffer.get_pixel(1, 1), Some(vec![10, 0, 0, 255]));
+        assert_eq!(buffer.get_pixel(5, 1), Some(vec![0, 10, 0, 255]));
+        assert_eq!(buffer.get_pixel(9, 5), Some(vec![255, 255, 255, 255]));
+        assert_eq!(buffer.get_loaded_tile_count(), 2);
+
+        buffer.resize_grid(1, 1).unwrap();
+        assert_eq!((buffer.width(), buffer.height()), (4, 4));
+        assert_eq!(buffer.get_loaded_tiles(), vec![0, 0]);
+        assert_eq!(buffer.get_pixel(1, 1), Some(vec![10, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_set_tile_size_reflows_tiles() {
+        let mut buffer = ImageBuffer::new(4, 4, 2, 1).unwrap();
+        buffer
+            .load_image_from_bytes(&solid_png(4, 4, [200, 0, 0, 255]), 0, 0)
+            .unwrap();
+        buffer.fill_tile_with_color(1, 0, 0, 200, 0, 255).unwrap();
+
+        buffer.set_tile_size(8, 4).unwrap();
+        assert_eq!((buffer.width(), buffer.height()), (16, 4));
+        assert_eq!((buffer.tile_width(), buffer.tile_height()), (8, 4));
+
+        // The retained source is re-fit and centered; the color fill is stretched
+        assert_eq!(buffer.get_pixel(4, 2), Some(vec![200, 0, 0, 255]));
+        assert_eq!(buffer.get_pixel(0, 2), Some(vec![255, 255, 255, 255]));
+        assert_eq!(buffer.get_pixel(8, 0), Some(vec![0, 200, 0, 255]));
+        assert_eq!(buffer.get_pixel(15, 3), Some(vec![0, 200, 0, 255]));
+        assert_eq!(buffer.get_loaded_tile_count(), 2);
+    }
+
+    #[test]
+    fn test_scale_and_offset_load_reports_drawn_rect() {
+        let mut buffer = ImageBuffer::new(8, 8, 2, 1).unwrap();
+        let png = solid_png(4, 2, [0, 0, 200, 255]);
+
+        // 4x2 fits as 8x4, centered vertically in the second tile
+        let drawn = buffer
+            .load_image_from_bytes_with_scale_and_offset(&png, 1, 0, 1.0, 0, 0)
+            .unwrap();
+        assert_eq!(drawn, vec![8, 2, 8, 4]);
+
+        // Shifting left crops the image at the tile edge
+        let drawn = buffer
+            .load_image_from_bytes_with_scale_and_offset(&png, 1, 0, 1.0, -3, 1)
+            .unwrap();
+        assert_eq!(drawn, vec![8, 3, 5, 4]);
+    }
+
+    #[test]
+    fn test_load_image_with_filter() {
+        // A 2x1 red/blue image upscaled with nearest-neighbor keeps hard edges
+        let img = image::RgbaImage::from_fn(2, 1, |x, _| {
+            if x == 0 {
+                image::Rgba([255, 0, 0, 255])
+            } else {
+                image::Rgba([0, 0, 255, 255])
+            }
+        });
+        let png = encode_png(img.as_raw(), 2, 1).unwrap();
+
+        let mut buffer = ImageBuffer::new(8, 4, 1, 1).unwrap();
+        buffer
+            .load_image_from_bytes_with_filter(&png, 0, 0, 1.0, ResizeFilter::Nearest)
+            .unwrap();
+        assert_eq!(buffer.get_pixel(3, 1), Some(vec![255, 0, 0, 255]));
+        assert_eq!(buffer.get_pixel(4, 1), Some(vec![0, 0, 255, 255]));
+    }
+
+    #[test]
+    fn test_proxy_tracks_buffer_changes() {
+        let mut buffer = ImageBuffer::new(4, 4, 2, 1).unwrap();
+        assert_eq!(buffer.proxy_len(), buffer.data_len());
+        assert_eq!(buffer.proxy_ptr(), buffer.data_ptr());
+
+        buffer.enable_proxy(2);
+        assert_eq!((buffer.proxy_width(), buffer.proxy_height()), (4, 2));
+        assert_eq!(buffer.proxy_len(), 4 * 2 * 4);
+
+        buffer.fill_tile_with_color(1, 0, 100, 0, 0, 255).unwrap();
+        buffer.set_pixel(0, 0, 0, 0, 0, 0);
+        buffer.proxy_ptr();
+        assert_eq!(&buffer.proxy[0..4], &[0, 0, 0, 0]);
+        assert_eq!(&buffer.proxy[8..12], &[100, 0, 0, 255]);
+
+        buffer.clear_tile(1, 0).unwrap();
+        buffer.set_pixel(0, 0, 200, 200, 200, 200);
+        buffer.proxy_ptr();
+        assert_eq!(&buffer.proxy[0..4], &[50, 50, 50, 50]);
+        assert_eq!(&buffer.proxy[8..12], &[255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_blend_load_composites_over_existing_pixels() {
+        let mut buffer = ImageBuffer::new(4, 4, 1, 1).unwrap();
+        buffer.fill_tile_with_color(0, 0, 0, 0, 200, 255).unwrap();
+
+        let half_red = solid_png(4, 4, [255, 0, 0, 128]);
+        buffer
+            .load_image_from_bytes_blend(&half_red, 0, 0, 1.0)
+            .unwrap();
+        assert_eq!(buffer.get_pixel(1, 1), Some(vec![128, 0, 100, 255]));
+
+        // The overwrite path still replaces the tile outright
+        buffer.load_image_from_bytes(&half_red, 0, 0).unwrap();
+        assert_eq!(buffer.get_pixel(1, 1), Some(vec![255, 0, 0, 128]));
+    }
+
+    #[test]
+    fn test_render_transparency_checker() {
+        let mut buffer = ImageBuffer::new(4, 4, 1, 1).unwrap();
+        buffer.set_pixel(3, 0, 9, 9, 9, 128);
+        buffer
+            .render_transparency_checker(2, &[200, 200, 200], &[100, 100, 100])
+            .unwrap();
+
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![200, 200, 200, 255]));
+        assert_eq!(buffer.get_pixel(2, 1), Some(vec![100, 100, 100, 255]));
+        assert_eq!(buffer.get_pixel(2, 2), Some(vec![200, 200, 200, 255]));
+        // Partially transparent pixels are kept as they are
+        assert_eq!(buffer.get_pixel(3, 0), Some(vec![9, 9, 9, 128]));
+    }
+
+    #[test]
+    fn test_background_color_round_trip() {
+        let mut buffer = ImageBuffer::new(2, 2, 1, 1).unwrap();
+        assert_eq!(buffer.background_color(), vec![255, 255, 255, 255]);
+
+        buffer.set_background_color(10, 20, 30, 40);
+        assert_eq!(buffer.background_color(), vec![10, 20, 30, 40]);
+    }
+
+    #[test]
+    fn test_layout_json_round_trip() {
+        let mut buffer = ImageBuffer::new_nonuniform(vec![4, 6], vec![5]).unwrap();
+        buffer.set_background_color(1, 2, 3, 255);
+        buffer
+            .load_image_from_bytes_with_scale_and_offset(
+                &solid_png(2, 2, [9, 9, 9, 255]),
+                1,
+                0,
+                0.5,
+                1,
+                -1,
+            )
+            .unwrap();
+        buffer
+            .set_tile_background_color(0, 0, 50, 60, 70, 255)
+            .unwrap();
+        buffer.lock_tile(0, 0).unwrap();
+
+        let json = buffer.export_layout_json();
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed["num_cols"], 2);
+        assert_eq!(parsed["col_widths"], serde_json::json!([4, 6]));
+        assert_eq!(parsed["tiles"].as_array().unwrap().len(), 2);
+
+        let mut restored = ImageBuffer::new(1, 1, 1, 1).unwrap();
+        restored.import_layout_json(&json).unwrap();
+        assert_eq!((restored.width(), restored.height()), (10, 5));
+        assert_eq!(restored.background_color(), vec![1, 2, 3, 255]);
+        assert_eq!(restored.get_loaded_tile_count(), 0);
+        // The per-tile background survives and is painted into the empty cell
+        assert_eq!(restored.get_pixel(0, 0), Some(vec![50, 60, 70, 255]));
+        assert_eq!(restored.get_pixel(5, 0), Some(vec![1, 2, 3, 255]));
+        assert!(restored.is_tile_locked(0, 0));
+        assert!(!restored.is_tile_locked(1, 0));
+    }
+
+    #[test]
+    fn test_tile_collage() {
+        let mut buffer = ImageBuffer::new(8, 4, 1, 1).unwrap();
+        buffer.begin_tile_collage(0, 0, 2, 1).unwrap();
+        buffer
+            .add_collage_image(&solid_png(4, 4, [200, 0, 0, 255]))
+            .unwrap();
+        assert_eq!(buffer.get_pixel(1, 1), Some(vec![200, 0, 0, 255]));
+        // The second sub-cell is still background
+        assert_eq!(buffer.get_pixel(6, 1), Some(vec![255, 255, 255, 255]));
+
+        buffer
+            .add_collage_image(&solid_png(4, 4, [0, 0, 200, 255]))
+            .unwrap();
+        assert_eq!(buffer.get_pixel(6, 1), Some(vec![0, 0, 200, 255]));
+        assert!(buffer.is_tile_loaded(0, 0));
+    }
+
+    #[test]
+    fn test_draw_and_clear_grid_lines() {
+        let mut buffer = ImageBuffer::new(4, 4, 2, 2).unwrap();
+        buffer.fill_background();
+        buffer.draw_grid_lines(2, 0, 0, 0, 255);
+
+        // Inner edge at 4 covers 3..5; outer edges are pulled inside the buffer
+        let black = Some(vec![0, 0, 0, 255]);
+        let white = Some(vec![255, 255, 255, 255]);
+        assert_eq!(buffer.get_pixel(3, 2), black);
+        assert_eq!(buffer.get_pixel(4, 6), black);
+        assert_eq!(buffer.get_pixel(0, 2), black);
+        assert_eq!(buffer.get_pixel(7, 7), black);
+        assert_eq!(buffer.get_pixel(2, 2), white);
+        assert_eq!(buffer.get_pixel(5, 5), white);
+
+        buffer.clear_grid_lines();
+        assert!(buffer.data.iter().all(|&v| v == 255));
+    }
+
+    #[test]
+    fn test_crop_tile() {
+        let mut buffer = ImageBuffer::new(4, 4, 2, 1).unwrap();
+        buffer.fill_tile_with_color(1, 0, 0, 100, 0, 255).unwrap();
+        buffer.crop_tile(1, 0, 1, 1, 2, 3).unwrap();
+
+        assert_eq!(buffer.get_pixel(5, 1), Some(vec![0, 100, 0, 255]));
+        assert_eq!(buffer.get_pixel(6, 3), Some(vec![0, 100, 0, 255]));
+        assert_eq!(buffer.get_pixel(4, 1), Some(vec![255, 255, 255, 255]));
+        assert_eq!(buffer.get_pixel(7, 1), Some(vec![255, 255, 255, 255]));
+        assert_eq!(buffer.get_pixel(5, 0), Some(vec![255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn test_repeat_mode_tiles_source() {
+        // 3x2 source: red column then two blue columns, repeated into a 7x5 tile
+        let img = image::RgbaImage::from_fn(3, 2, |x, _| {
+            if x == 0 {
+                image::Rgba([255, 0, 0, 255])
+            } else {
+                image::Rgba([0, 0, 255, 255])
+            }
+        });
+        let png = encode_png(img.as_raw(), 3, 2).unwrap();
+
+        let mut buffer = ImageBuffer::new(7, 5, 1, 1).unwrap();
+        buffer
+            .load_image_from_bytes_with_mode(&png, 0, 0, ScaleMode::Repeat)
+            .unwrap();
+        for (x, y) in [(0, 0), (3, 2), (6, 4)] {
+            assert_eq!(buffer.get_pixel(x, y), Some(vec![255, 0, 0, 255]));
+        }
+        for (x, y) in [(1, 0), (5, 3)] {
+            assert_eq!(buffer.get_pixel(x, y), Some(vec![0, 0, 255, 255]));
+        }
+    }
+
+    #[test]
+    fn test_remove_color_key() {
+        let mut buffer = ImageBuffer::new(4, 4, 1, 1).unwrap();
+        buffer
+            .fill_tile_with_color(0, 0, 250, 250, 250, 255)
+            .unwrap();
+        buffer.set_pixel(1, 1, 10, 20, 30, 255);
+
+        // Exact match only with tolerance 0
+        buffer.remove_color_key(0, 0, 255, 255, 255, 0).unwrap();
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![250, 250, 250, 255]));
+
+        buffer.remove_color_key(0, 0, 255, 255, 255, 5).unwrap();
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![250, 250, 250, 0]));
+        assert_eq!(buffer.get_pixel(1, 1), Some(vec![10, 20, 30, 255]));
+    }
+
+    #[test]
+    fn test_tint_tile() {
+        let mut buffer = ImageBuffer::new(2, 2, 1, 1).unwrap();
+        buffer
+            .fill_tile_with_color(0, 0, 255, 255, 255, 200)
+            .unwrap();
+
+        buffer.tint_tile(0, 0, 255, 255, 255).unwrap();
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![255, 255, 255, 200]));
+
+        buffer.tint_tile(0, 0, 128, 0, 0).unwrap();
+        assert_eq!(buffer.get_pixel(1, 1), Some(vec![128, 0, 0, 200]));
+    }
+
+    #[test]
+    fn test_sharpen_tile() {
+        let mut buffer = ImageBuffer::new(3, 3, 1, 1).unwrap();
+        buffer
+            .fill_tile_with_color(0, 0, 100, 100, 100, 255)
+            .unwrap();
+        buffer.set_pixel(1, 1, 150, 150, 150, 255);
+        let before = buffer.data.clone();
+
+        buffer.sharpen_tile(0, 0, 0.0).unwrap();
+        assert_eq!(buffer.data, before);
+
+        // The bright center gets brighter and its neighbors darker; alpha is untouched
+        buffer.sharpen_tile(0, 0, 1.0).unwrap();
+        assert_eq!(buffer.get_pixel(1, 1), Some(vec![255, 255, 255, 255]));
+        assert_eq!(buffer.get_pixel(1, 0), Some(vec![50, 50, 50, 255]));
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![100, 100, 100, 255]));
+    }
+
+    #[test]
+    fn test_invert_tile_twice_restores_pixels() {
+        let mut buffer = ImageBuffer::new(2, 2, 2, 1).unwrap();
+        buffer
+            .fill_tile_with_color(0, 0, 10, 100, 250, 128)
+            .unwrap();
+        let before = buffer.data.clone();
+
+        buffer.invert_tile(0, 0).unwrap();
+        assert_eq!(buffer.get_pixel(1, 1), Some(vec![245, 155, 5, 128]));
+        // The neighboring tile is untouched
+        assert_eq!(buffer.get_pixel(2, 0), Some(vec![0, 0, 0, 0]));
+
+        buffer.invert_tile(0, 0).unwrap();
+        assert_eq!(buffer.data, before);
+    }
+
+    #[test]
+    fn test_sepia_tile() {
+        let mut buffer = ImageBuffer::new(2, 2, 1, 1).unwrap();
+        buffer
+            .fill_tile_with_color(0, 0, 128, 128, 128, 200)
+            .unwrap();
+        buffer.sepia_tile(0, 0).unwrap();
+
+        // Each output channel is the row sum of the sepia matrix times 128
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![173, 154, 120, 200]));
+    }
+
+    #[test]
+    fn test_get_tile_pixels() {
+        let mut buffer = ImageBuffer::with_gutter(2, 3, 2, 1, 1).unwrap();
+        buffer.fill_tile_with_color(1, 0, 7, 8, 9, 255).unwrap();
+
+        let pixels = buffer.get_tile_pixels(1, 0).unwrap();
+        assert_eq!(pixels.len(), 2 * 3 * 4);
+        assert_eq!(pixels, [7, 8, 9, 255].repeat(6));
+
+        // Round trip through the raw RGBA loader
+        let mut other = ImageBuffer::new(2, 3, 1, 1).unwrap();
+        other
+            .load_image_from_rgba(&pixels, 2, 3, 0, 0, 1.0)
+            .unwrap();
+        assert_eq!(other.get_tile_pixels(0, 0).unwrap(), pixels);
+    }
+
+    #[test]
+    fn test_letterbox_color_fills_bars_only() {
+        let mut buffer = ImageBuffer::new(8, 4, 2, 1).unwrap();
+        buffer.set_letterbox_color(0, 0, 0, 255);
+        buffer.fill_background();
+        buffer
+            .load_image_from_bytes(&solid_png(4, 4, [200, 0, 0, 255]), 0, 0)
+            .unwrap();
+
+        assert_eq!(buffer.get_pixel(1, 1), Some(vec![200, 0, 0, 255]));
+        assert_eq!(buffer.get_pixel(6, 1), Some(vec![0, 0, 0, 255]));
+        // Empty tiles keep the background
+        assert_eq!(buffer.get_pixel(12, 1), Some(vec![255, 255, 255, 255]));
+
+        buffer.clear_letterbox_color();
+        buffer.rescale_tile(0, 0, 1.0).unwrap();
+        assert_eq!(buffer.get_pixel(7, 1), Some(vec![255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn test_apply_rounded_corners() {
+        let mut buffer = ImageBuffer::new(8, 6, 1, 1).unwrap();
+        buffer.fill_tile_with_color(0, 0, 9, 9, 9, 255).unwrap();
+        // Clamped to 3, half of the 6px height
+        buffer.apply_rounded_corners(0, 0, 10).unwrap();
+
+        // Corner pixels are masked out on every side; pixels inside the curves stay opaque
+        for (x, y) in [(0, 0), (7, 0), (0, 5), (7, 5)] {
+            assert_eq!(buffer.get_pixel(x, y).unwrap()[3], 0, "({}, {})", x, y);
+        }
+        for (x, y) in [(4, 0), (1, 3), (3, 3), (6, 2)] {
+            assert_eq!(buffer.get_pixel(x, y).unwrap()[3], 255, "({}, {})", x, y);
+        }
+        // The curve itself is partially covered
+        let edge = buffer.get_pixel(1, 0).unwrap()[3];
+        assert!(edge > 0 && edge < 255);
+    }
+
+    #[test]
+    fn test_draw_tile_shadow() {
+        let mut buffer = ImageBuffer::with_gutter(4, 4, 2, 1, 4).unwrap();
+        buffer.fill_background();
+        buffer.fill_tile_with_color(0, 0, 200, 0, 0, 255).unwrap();
+        buffer
+            .draw_tile_shadow(0, 0, 2, 0, 0, 0, 0, 0, 255)
+            .unwrap();
+
+        // The tile stays in front, the shifted shadow lands in the gutter
+        assert_eq!(buffer.get_pixel(3, 1), Some(vec![200, 0, 0, 255]));
+        assert_eq!(buffer.get_pixel(5, 1), Some(vec![0, 0, 0, 255]));
+        assert_eq!(buffer.get_pixel(6, 1), Some(vec![255, 255, 255, 255]));
+
+        // Blurring softens the edge into partial coverage
+        buffer.fill_background();
+        buffer
+            .draw_tile_shadow(0, 0, 0, 0, 2, 0, 0, 0, 255)
+            .unwrap();
+        let soft = buffer.get_pixel(5, 1).unwrap()[0];
+        assert!(soft > 0 && soft < 255);
+
+        // Extreme blur and offsets are clamped instead of allocating a huge mask
+        buffer.fill_background();
+        buffer
+            .draw_tile_shadow(0, 0, i32::MIN, i32::MAX, u32::MAX, 0, 0, 0, 255)
+            .unwrap();
+        assert_eq!(buffer.get_pixel(5, 1), Some(vec![255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn test_decode_image_downscaled() {
+        let mut buffer = ImageBuffer::new(64, 32, 1, 1).unwrap();
+        buffer.fill_tile_with_color(0, 0, 40, 80, 120, 255).unwrap();
+        let jpeg = buffer.export_to_jpeg(90).unwrap();
+        let png = buffer.export_to_png().unwrap();
+
+        for bytes in [&jpeg, &png] {
+            let img = decode_image_downscaled(bytes, 10).unwrap();
+            assert_eq!(img.dimensions(), (10, 5));
+        }
+        // Small images are left at their native size
+        assert_eq!(
+            decode_image_downscaled(&png, 100).unwrap().dimensions(),
+            (64, 32)
+        );
+
+        let mut target = ImageBuffer::new(8, 4, 1, 1).unwrap();
+        target.load_image_downscaled(&jpeg, 0, 0, 16).unwrap();
+        assert!(target.is_tile_loaded(0, 0));
+        let pixel = target.get_pixel(4, 2).unwrap();
+        assert!(pixel[2] > 100 && pixel[0] < 60);
+    }
+
+    #[test]
+    fn test_load_image_with_padding() {
+        let mut buffer = ImageBuffer::new(10, 10, 1, 1).unwrap();
+        buffer.set_letterbox_color(0, 0, 0, 255);
+        let png = solid_png(4, 2, [0, 150, 0, 255]);
+        buffer.load_image_with_padding(&png, 0, 0, 1.0, 1).unwrap();
+
+        // 8x4 image centered in the 8x8 inner area: rows 3..7
+        assert_eq!(buffer.get_pixel(1, 3), Some(vec![0, 150, 0, 255]));
+        assert_eq!(buffer.get_pixel(8, 6), Some(vec![0, 150, 0, 255]));
+        // Bars inside the padded area use the letterbox color, the padding stays background
+        assert_eq!(buffer.get_pixel(4, 2), Some(vec![0, 0, 0, 255]));
+        assert_eq!(buffer.get_pixel(0, 4), Some(vec![255, 255, 255, 255]));
+        assert_eq!(buffer.get_pixel(4, 9), Some(vec![255, 255, 255, 255]));
+
+        // Upscaled content is clipped at the padding
+        buffer.load_image_with_padding(&png, 0, 0, 2.0, 2).unwrap();
+        assert_eq!(buffer.get_pixel(1, 5), Some(vec![255, 255, 255, 255]));
+        assert_eq!(buffer.get_pixel(2, 5), Some(vec![0, 150, 0, 255]));
+    }
+
+    #[test]
+    fn test_load_image_with_anchor() {
+        let png = solid_png(4, 2, [0, 0, 150, 255]);
+        let blue = Some(vec![0, 0, 150, 255]);
+        let white = Some(vec![255, 255, 255, 255]);
+
+        // 8x4 image in an 8x8 tile
+        let mut buffer = ImageBuffer::new(8, 8, 1, 1).unwrap();
+        buffer
+            .load_image_with_anchor(&png, 0, 0, 1.0, Anchor::Top)
+            .unwrap();
+        assert_eq!(buffer.get_pixel(0, 0), blue);
+        assert_eq!(buffer.get_pixel(0, 4), white);
+
+        buffer
+            .load_image_with_anchor(&png, 0, 0, 1.0, Anchor::BottomRight)
+            .unwrap();
+        assert_eq!(buffer.get_pixel(0, 3), white);
+        assert_eq!(buffer.get_pixel(7, 7), blue);
+
+        // Center matches the centered mode placement
+        let mut centered = ImageBuffer::new(8, 8, 1, 1).unwrap();
+        centered
+            .load_image_from_bytes_with_mode(&png, 0, 0, ScaleMode::Fit)
+            .unwrap();
+        buffer
+            .load_image_with_anchor(&png, 0, 0, 1.0, Anchor::Center)
+            .unwrap();
+        assert_eq!(buffer.data, centered.data);
+    }
+
+    #[test]
+    fn test_all_tile_draw_bounds() {
+        let mut buffer = ImageBuffer::new(8, 8, 2, 2).unwrap();
+        let png = solid_png(4, 2, [1, 2, 3, 255]);
+        buffer
+            .load_image_from_bytes_with_mode(&png, 1, 0, ScaleMode::Fit)
+            .unwrap();
+        buffer.fill_tile_with_color(0, 1, 0, 0, 0, 255).unwrap();
+        assert_eq!(
+            buffer.all_tile_draw_bounds(),
+            vec![1, 0, 8, 2, 8, 4, 0, 1, 0, 8, 8, 8]
+        );
+
+        // Moving and cropping carry the rectangle along
+        buffer.move_tile(1, 0, 1, 1).unwrap();
+        buffer.crop_tile(1, 1, 0, 0, 8, 5).unwrap();
+        assert_eq!(&buffer.all_tile_draw_bounds()[6..], &[1, 1, 8, 10, 8, 3]);
+
+        buffer.flip_tile_vertical(1, 1).unwrap();
+        assert_eq!(&buffer.all_tile_draw_bounds()[6..], &[1, 1, 8, 11, 8, 3]);
+    }
+
+    #[test]
+    fn test_undo_snapshots() {
+        let mut buffer = ImageBuffer::new(2, 2, 1, 1).unwrap();
+        assert!(!buffer.undo());
+
+        buffer.push_undo_snapshot();
+        buffer.fill_tile_with_color(0, 0, 5, 5, 5, 255).unwrap();
+        assert!(buffer.undo());
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![0, 0, 0, 0]));
+        assert!(!buffer.is_tile_loaded(0, 0));
+
+        // Only the newest MAX_UNDO_SNAPSHOTS survive
+        for value in 0..(MAX_UNDO_SNAPSHOTS + 2) as u8 {
+            buffer.set_pixel(0, 0, value, 0, 0, 255);
+            buffer.push_undo_snapshot();
+        }
+        let mut undone = 0;
+        while buffer.undo() {
+            undone += 1;
+        }
+        assert_eq!(undone, MAX_UNDO_SNAPSHOTS);
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![2, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_tile_checksum() {
+        let mut buffer = ImageBuffer::new(3, 3, 2, 1).unwrap();
+        buffer.fill_background();
+        let empty = buffer.tile_checksum(0, 0).unwrap();
+        assert_eq!(buffer.tile_checksum(1, 0).unwrap(), empty);
+
+        buffer.set_pixel(4, 2, 255, 255, 255, 254);
+        assert_eq!(buffer.tile_checksum(0, 0).unwrap(), empty);
+        assert_ne!(buffer.tile_checksum(1, 0).unwrap(), empty);
+
+        buffer.set_pixel(4, 2, 255, 255, 255, 255);
+        assert_eq!(buffer.tile_checksum(1, 0).unwrap(), empty);
+    }
+
+    #[test]
+    fn test_rotate_canvas_transposes_grid() {
+        let mut buffer = ImageBuffer::with_gutter(4, 2, 3, 1, 1).unwrap();
+        buffer.fill_tile_with_color(0, 0, 200, 0, 0, 255).unwrap();
+        buffer.set_pixel(0, 0, 0, 0, 200, 255);
+
+        buffer.rotate_canvas(1);
+        assert_eq!((buffer.width(), buffer.height()), (2, 14));
+        assert_eq!((buffer.tile_width(), buffer.tile_height()), (2, 4));
+        assert_eq!(buffer.tile_at(0, 0), Some(vec![0, 0]));
+        assert_eq!(buffer.tile_at(0, 5), Some(vec![0, 1]));
+        assert_eq!(buffer.get_loaded_tiles(), vec![0, 0]);
+        // The old top-left corner is now the top-right corner
+        assert_eq!(buffer.get_pixel(1, 0), Some(vec![0, 0, 200, 255]));
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![200, 0, 0, 255]));
+
+        // Five turns equal one more; four turns in total are back to the start
+        buffer.rotate_canvas(5);
+        buffer.rotate_canvas(2);
+        assert_eq!((buffer.width(), buffer.height()), (14, 2));
+        assert_eq!(buffer.get_loaded_tiles(), vec![0, 0]);
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![0, 0, 200, 255]));
+    }
+
+    #[test]
+    fn test_export_to_webp_lossless_round_trip() {
+        let mut buffer = ImageBuffer::new(3, 2, 2, 1).unwrap();
+        buffer.fill_tile_with_color(1, 0, 10, 20, 30, 128).unwrap();
+        let webp_bytes = buffer.export_to_webp(true, 0.0).unwrap();
+
+        let decoded = image::load_from_memory(&webp_bytes).unwrap().to_rgba8();
+        assert_eq!(decoded.dimensions(), (6, 2));
+        assert_eq!(decoded.as_raw(), &buffer.data);
+    }
+
+    #[cfg(feature = "webp-lossy")]
+    #[test]
+    fn test_export_to_webp_lossy() {
+        let buffer = ImageBuffer::new(8, 8, 2, 2).unwrap();
+        let webp_bytes = buffer.export_to_webp(false, 80.0).unwrap();
+        let decoded = image::load_from_memory(&webp_bytes).unwrap();
+        assert_eq!(decoded.dimensions(), (16, 16));
+    }
+
+    #[test]
+    fn test_composite_buffer() {
+        let mut base = ImageBuffer::new(4, 4, 1, 1).unwrap();
+        base.fill_tile_with_color(0, 0, 0, 0, 200, 255).unwrap();
+
+        let mut layer = ImageBuffer::new(2, 2, 1, 1).unwrap();
+        layer.fill_tile_with_color(0, 0, 200, 0, 0, 255).unwrap();
+        layer.set_pixel(0, 0, 0, 0, 0, 0);
+
+        // Hangs off the bottom-right corner; only its transparent top-left pixel overlaps
+        base.composite_buffer(&layer, 3, 3);
+        assert_eq!(base.get_pixel(3, 3), Some(vec![0, 0, 200, 255]));
+
+        base.composite_buffer(&layer, -1, -1);
+        assert_eq!(base.get_pixel(0, 0), Some(vec![200, 0, 0, 255]));
+        assert_eq!(base.get_pixel(1, 1), Some(vec![0, 0, 200, 255]));
+    }
+
+    #[test]
+    fn test_vignette_tile() {
+        let mut buffer = ImageBuffer::new(9, 9, 1, 1).unwrap();
+        buffer
+            .fill_tile_with_color(0, 0, 200, 100, 50, 255)
+            .unwrap();
+
+        buffer.vignette_tile(0, 0, 0.0).unwrap();
+        assert!(buffer
+            .data
+            .chunks_exact(4)
+            .all(|p| p == [200, 100, 50, 255]));
+
+        buffer.vignette_tile(0, 0, 1.0).unwrap();
+        assert_eq!(buffer.get_pixel(4, 4), Some(vec![200, 100, 50, 255]));
+        let corner = buffer.get_pixel(0, 0).unwrap();
+        assert!(corner[0] < 50 && corner[3] == 255);
+    }
+
+    #[test]
+    fn test_draw_caption_bar() {
+        let mut buffer = ImageBuffer::new(4, 4, 1, 1).unwrap();
+        buffer.fill_tile_with_color(0, 0, 0, 0, 200, 255).unwrap();
+
+        buffer
+            .draw_caption_bar(0, 0, 1, 255, 255, 255, 255)
+            .unwrap();
+        assert_eq!(buffer.get_pixel(0, 3), Some(vec![255, 255, 255, 255]));
+        assert_eq!(buffer.get_pixel(3, 3), Some(vec![255, 255, 255, 255]));
+        assert_eq!(buffer.get_pixel(0, 2), Some(vec![0, 0, 200, 255]));
+
+        // Fully transparent bars leave the tile untouched
+        buffer.draw_caption_bar(0, 0, 4, 255, 0, 0, 0).unwrap();
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![0, 0, 200, 255]));
+    }
+
+    #[test]
+    fn test_load_pixel_art() {
+        // 2x2 source: red, green / blue, yellow
+        let src: Vec<u8> = [
+            [255, 0, 0, 255],
+            [0, 255, 0, 255],
+            [0, 0, 255, 255],
+            [255, 255, 0, 255],
+        ]
+        .concat();
+        let png = encode_png(&src, 2, 2).unwrap();
+
+        // Auto-picks a factor of 3 for a 6x6 tile
+        let mut buffer = ImageBuffer::new(6, 6, 1, 1).unwrap();
+        buffer.load_pixel_art(&png, 0, 0, 0).unwrap();
+        assert_eq!(buffer.get_pixel(2, 2), Some(vec![255, 0, 0, 255]));
+        assert_eq!(buffer.get_pixel(3, 2), Some(vec![0, 255, 0, 255]));
+        assert_eq!(buffer.get_pixel(5, 5), Some(vec![255, 255, 0, 255]));
+
+        // A factor of 2 is centered with a 1px band of background around it
+        buffer.load_pixel_art(&png, 0, 0, 2).unwrap();
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![255, 255, 255, 255]));
+        assert_eq!(buffer.get_pixel(1, 1), Some(vec![255, 0, 0, 255]));
+        assert_eq!(buffer.get_pixel(4, 4), Some(vec![255, 255, 0, 255]));
+
+        // A factor of 5 overflows the tile and is cropped around the center
+        buffer.load_pixel_art(&png, 0, 0, 5).unwrap();
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![255, 0, 0, 255]));
+        assert_eq!(buffer.get_pixel(5, 5), Some(vec![255, 255, 0, 255]));
+    }
+
+    #[test]
+    fn test_is_tile_modified() {
+        let mut buffer = ImageBuffer::new(4, 4, 2, 1).unwrap();
+        let png = encode_png(&[255, 0, 0, 255].repeat(4), 2, 2).unwrap();
+        assert!(!buffer.is_tile_modified(0, 0));
+
+        buffer.load_image_from_bytes(&png, 0, 0).unwrap();
+        assert!(!buffer.is_tile_modified(0, 0));
+
+        buffer.invert_tile(0, 0).unwrap();
+        assert!(buffer.is_tile_modified(0, 0));
+        assert!(!buffer.is_tile_modified(1, 0));
+
+        // Moving keeps the flag; a fresh load resets it
+        buffer.move_tile(0, 0, 1, 0).unwrap();
+        assert!(buffer.is_tile_modified(1, 0));
+        buffer.load_image_from_bytes(&png, 1, 0).unwrap();
+        assert!(!buffer.is_tile_modified(1, 0));
+
+        buffer.set_tile_opacity(1, 0, 0.5).unwrap();
+        assert!(buffer.is_tile_modified(1, 0));
+        buffer.load_image_from_bytes(&png, 1, 0).unwrap();
+        buffer
+            .draw_tile_shadow(1, 0, 1, 1, 0, 0, 0, 0, 255)
+            .unwrap();
+        assert!(buffer.is_tile_modified(1, 0));
+    }
+
+    #[test]
+    fn test_load_batch() {
+        let red = encode_png(&[255, 0, 0, 255].repeat(4), 2, 2).unwrap();
+        let blue = encode_png(&[0, 0, 255, 255].repeat(4), 2, 2).unwrap();
+        let blob = [red.clone(), blue.clone()].concat();
+        let manifest = [
+            0,
+            0,
+            0,
+            red.len() as u32,
+            1,
+            0,
+            red.len() as u32,
+            blue.len() as u32,
+            // Runs past the end of the blob
+            0,
+            1,
+            blob.len() as u32,
+            1,
+        ];
+
+        let mut buffer = ImageBuffer::new(2, 2, 2, 2).unwrap();
+        let errors = buffer.load_batch(&blob, &manifest).unwrap();
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![255, 0, 0, 255]));
+        assert_eq!(buffer.get_pixel(2, 0), Some(vec![0, 0, 255, 255]));
+        assert!(!buffer.is_tile_loaded(0, 1));
+
+        let errors: serde_json::Value = serde_json::from_str(&errors).unwrap();
+        assert_eq!(errors.as_array().unwrap().len(), 1);
+        assert_eq!(errors[0]["index"], 2);
+    }
+
+    #[test]
+    fn test_fit_width_and_height_modes() {
+        // A tall 2x4 image
+        let png = encode_png(&[255, 0, 0, 255].repeat(8), 2, 4).unwrap();
+        let mut buffer = ImageBuffer::new(4, 4, 2, 1).unwrap();
+
+        // Matching the width makes it 4x8, cropped to cover the whole tile
+        buffer
+            .load_image_from_bytes_with_mode(&png, 0, 0, ScaleMode::FitWidth)
+            .unwrap();
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![255, 0, 0, 255]));
+        assert_eq!(buffer.get_pixel(3, 3), Some(vec![255, 0, 0, 255]));
+
+        // Matching the height makes it 2x4, centered between background bars
+        buffer
+            .load_image_from_bytes_with_mode(&png, 1, 0, ScaleMode::FitHeight)
+            .unwrap();
+        assert_eq!(buffer.get_pixel(4, 0), Some(vec![255, 255, 255, 255]));
+        assert_eq!(buffer.get_pixel(5, 0), Some(vec![255, 0, 0, 255]));
+        assert_eq!(buffer.get_pixel(6, 3), Some(vec![255, 0, 0, 255]));
+        assert_eq!(buffer.get_pixel(7, 3), Some(vec![255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn test_to_rgba8_dithered_reduces_banding() {
+        // A flat field halfway between 8-bit levels 10 and 11, as in a slow 16-bit gradient
+        let level = 257 * 10 + 128;
+        let img = DynamicImage::ImageRgba16(image::ImageBuffer::from_pixel(
+            8,
+            8,
+            image::Rgba([level, level, level, u16::MAX]),
+        ));
+        let mean_red =
+            |pixels: &image::RgbaImage| pixels.pixels().map(|p| p[0] as f32).sum::<f32>() / 64.0;
+
+        // Plain truncation snaps every pixel to one level; dithering keeps the average
+        let truncated = mean_red(&img.to_rgba8());
+        let dithered = to_rgba8_dithered(&img);
+        assert!((truncated - 10.5).abs() >= 0.4);
+        assert!((mean_red(&dithered) - 10.5).abs() < 0.1);
+        assert!(dithered.pixels().all(|p| p[3] == 255));
+
+        // Exact 8-bit levels pass through unchanged
+        let exact = DynamicImage::ImageRgba16(image::ImageBuffer::from_pixel(
+            4,
+            4,
+            image::Rgba([257 * 10, 0, u16::MAX, u16::MAX]),
+        ));
+        assert!(to_rgba8_dithered(&exact)
+            .pixels()
+            .all(|p| p.0 == [10, 0, 255, 255]));
+    }
+
+    #[test]
+    fn test_feather_tile() {
+        let png = encode_png(&[0, 0, 0, 255].repeat(16), 4, 4).unwrap();
+        let mut buffer = ImageBuffer::new(8, 8, 1, 1).unwrap();
+        buffer.load_image_from_bytes(&png, 0, 0).unwrap();
+        let before = buffer.data.clone();
+
+        buffer.feather_tile(0, 0, 0).unwrap();
+        assert_eq!(buffer.data, before);
+
+        // The image is fitted to fill the tile; its outer pixels fade most toward the white background
+        buffer.feather_tile(0, 0, 2).unwrap();
+        let edge = buffer.get_pixel(0, 3).unwrap();
+        let inner = buffer.get_pixel(1, 3).unwrap();
+        assert!(edge[0] > inner[0] && inner[0] > 0);
+        assert_eq!(buffer.get_pixel(3, 3), Some(vec![0, 0, 0, 255]));
+        assert!(buffer.is_tile_modified(0, 0));
+    }
+
+    #[test]
+    fn test_generate_pattern_rows_matches_full_pattern() {
+        let png = encode_png(&[0, 0, 0, 255].repeat(4), 2, 2).unwrap();
+        let mut full = ImageBuffer::new(5, 7, 2, 2).unwrap();
+        full.load_image_from_bytes(&png, 1, 0).unwrap();
+        let mut banded = ImageBuffer::new(5, 7, 2, 2).unwrap();
+        banded.load_image_from_bytes(&png, 1, 0).unwrap();
+
+        full.generate_pattern(3);
+        for start_row in (0..14).step_by(4) {
+            banded.generate_pattern_rows(3, start_row, 4);
+        }
+        assert_eq!(banded.data, full.data);
+    }
+
+    #[test]
+    fn test_tile_average_color() {
+        let mut buffer = ImageBuffer::new(2, 2, 2, 1).unwrap();
+        buffer.fill_tile_with_color(0, 0, 255, 0, 0, 255).unwrap();
+        assert_eq!(
+            buffer.tile_average_color(0, 0).unwrap(),
+            vec![255, 0, 0, 255]
+        );
+
+        // Transparent pixels lower the alpha but not the color
+        buffer.set_pixel(0, 0, 0, 0, 255, 0);
+        assert_eq!(
+            buffer.tile_average_color(0, 0).unwrap(),
+            vec![255, 0, 0, 191]
+        );
+
+        buffer.fill_tile_with_color(1, 0, 0, 0, 0, 0).unwrap();
+        assert_eq!(
+            buffer.tile_average_color(1, 0).unwrap(),
+            vec![255, 255, 255, 255]
+        );
+    }
+
+    #[test]
+    fn test_tile_histogram() {
+        let mut buffer = ImageBuffer::new(2, 2, 2, 1).unwrap();
+        buffer.fill_tile_with_color(0, 0, 255, 10, 0, 255).unwrap();
+        buffer.set_pixel(0, 0, 0, 0, 0, 0);
+
+        let bins = buffer.tile_histogram(0, 0).unwrap();
+        assert_eq!(bins.len(), 768);
+        assert_eq!(bins[255], 3);
+        assert_eq!(bins[256 + 10], 3);
+        assert_eq!(bins[512], 3);
+        assert_eq!(bins.iter().sum::<u32>(), 9);
+    }
+
+    #[test]
+    fn test_apply_levels_tile() {
+        let mut buffer = ImageBuffer::new(4, 1, 1, 1).unwrap();
+        for (x, value) in [0, 64, 128, 255].into_iter().enumerate() {
+            buffer.set_pixel(x as u32, 0, value, value, value, 200);
+        }
+        let before = buffer.data.clone();
+
+        buffer.apply_levels_tile(0, 0, 0, 255, 1.0, 0, 255).unwrap();
+        assert_eq!(buffer.data, before);
+
+        // Values below the input black point clip; the rest stretch into the output range
+        buffer
+            .apply_levels_tile(0, 0, 64, 255, 1.0, 10, 200)
+            .unwrap();
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![10, 10, 10, 200]));
+        assert_eq!(buffer.get_pixel(1, 0), Some(vec![10, 10, 10, 200]));
+        assert_eq!(buffer.get_pixel(3, 0), Some(vec![200, 200, 200, 200]));
+    }
+
+    #[test]
+    fn test_layout_size_overflow_is_detected() {
+        assert_eq!(layout_extent(&[400, 400], 10), Some(810));
+        assert_eq!(layout_extent(&[], 10), Some(0));
+        assert_eq!(layout_extent(&[u32::MAX / 2, u32::MAX / 2], 2), None);
+        assert_eq!(layout_extent(&[5000; 4], 0), Some(20000));
+
+        assert_eq!(buffer_len(20000, 20000), Some(1_600_000_000));
+        assert_eq!(buffer_len(u32::MAX, u32::MAX), None);
+
+        // Existing small grids are unaffected
+        let buffer = ImageBuffer::new(400, 400, 2, 2).unwrap();
+        assert_eq!(buffer.data.len(), 800 * 800 * 4);
+    }
+
+    #[test]
+    fn test_image_filling_buffer_writes_last_pixel() {
+        let png = encode_png(&[10, 20, 30, 255].repeat(9), 3, 3).unwrap();
+        let mut buffer = ImageBuffer::new(3, 3, 1, 1).unwrap();
+        buffer.load_image_from_bytes(&png, 0, 0).unwrap();
+
+        assert_eq!(buffer.get_pixel(2, 2), Some(vec![10, 20, 30, 255]));
+        assert_eq!(&buffer.data[buffer.data.len() - 4..], &[10, 20, 30, 255]);
+    }
+
+    #[test]
+    fn test_fit_leaves_no_seam_on_constrained_axis() {
+        // 13 / 11 in f32 truncates 11 * scale to 12, which used to leave a background column
+        let png = encode_png(&[0, 0, 200, 255].repeat(55), 11, 5).unwrap();
+        let mut buffer = ImageBuffer::new(13, 7, 1, 1).unwrap();
+        buffer.load_image_from_bytes(&png, 0, 0).unwrap();
+
+        assert_eq!(buffer.get_pixel(0, 2), Some(vec![0, 0, 200, 255]));
+        assert_eq!(buffer.get_pixel(12, 2), Some(vec![0, 0, 200, 255]));
+
+        // The free axis rounds to 6 of 7 rows; the leftover row always lands at the bottom
+        assert_eq!(buffer.get_pixel(6, 0), Some(vec![0, 0, 200, 255]));
+        assert_eq!(buffer.get_pixel(6, 6), Some(vec![255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn test_check_scale() {
+        assert!(check_scale(1.0).is_ok());
+        assert!(check_scale(0.25).is_ok());
+        assert!(check_scale(MAX_SCALE).is_ok());
+        assert_eq!(
+            check_scale(0.0),
+            Err("Scale must be greater than 0 and at most 100, got 0".to_string())
+        );
+        assert!(check_scale(-1.5).is_err());
+        assert!(check_scale(f32::NAN).is_err());
+        assert!(check_scale(f32::INFINITY).is_err());
+        assert!(check_scale(MAX_SCALE * 2.0).is_err());
+    }
+
+    #[test]
+    fn test_load_image_spanning() {
+        // A wide 10x5 image over a 2x2 block of 4x4 cells with 2px gutters (a 10x10 block)
+        let png = encode_png(&[255, 0, 0, 255].repeat(50), 10, 5).unwrap();
+        let mut buffer = ImageBuffer::with_gutter(4, 4, 3, 2, 2).unwrap();
+        buffer.load_image_spanning(&png, 1, 0, 2, 2, 1.0).unwrap();
+        assert!(buffer.is_tile_loaded(1, 0) && buffer.is_tile_loaded(2, 1));
+        assert!(!buffer.is_tile_loaded(0, 0));
+
+        // Fitted to 10x5 and centered vertically in the block, rows 2..7 across both columns
+        assert_eq!(buffer.get_pixel(6, 1), Some(vec![255, 255, 255, 255]));
+        assert_eq!(buffer.get_pixel(6, 2), Some(vec![255, 0, 0, 255]));
+        assert_eq!(buffer.get_pixel(15, 3), Some(vec![255, 0, 0, 255]));
+        assert_eq!(buffer.get_pixel(15, 6), Some(vec![255, 0, 0, 255]));
+        assert_eq!(buffer.get_pixel(15, 7), Some(vec![255, 255, 255, 255]));
+        // The gutter between the spanned cells stays background
+        assert_eq!(buffer.get_pixel(10, 3), Some(vec![255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn test_rotate_tile_angle() {
+        let mut buffer = ImageBuffer::new(4, 4, 2, 1).unwrap();
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (3, 3)] {
+            buffer.set_pixel(x, y, 200, 0, 0, 255);
+            buffer.set_pixel(x + 4, y, 200, 0, 0, 255);
+        }
+        let before = buffer.data.clone();
+
+        buffer.rotate_tile_angle(0, 0, 0.0).unwrap();
+        buffer.rotate_tile_angle(0, 0, 360.0).unwrap();
+        assert_eq!(buffer.data, before);
+
+        // A right angle lands exactly on pixel centers and matches the quarter-turn rotation
+        buffer.rotate_tile_angle(0, 0, 90.0).unwrap();
+        buffer.rotate_tile(1, 0, 1).unwrap();
+        assert_eq!(buffer.read_tile_region(0, 0), buffer.read_tile_region(1, 0));
+
+        // At 45 degrees the exposed corners are background
+        buffer.rotate_tile_angle(0, 0, 45.0).unwrap();
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn test_posterize_tile() {
+        let mut buffer = ImageBuffer::new(3, 1, 1, 1).unwrap();
+        buffer.set_pixel(0, 0, 10, 100, 130, 77);
+        buffer.set_pixel(1, 0, 200, 250, 60, 255);
+        let before = buffer.data.clone();
+
+        buffer.posterize_tile(0, 0, 0).unwrap();
+        assert_eq!(buffer.data, before);
+
+        // Levels of 1 are clamped to 2: every channel snaps to 0 or 255
+        buffer.posterize_tile(0, 0, 1).unwrap();
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![0, 0, 255, 77]));
+        assert_eq!(buffer.get_pixel(1, 0), Some(vec![255, 255, 0, 255]));
+    }
+
+    #[test]
+    fn test_pixelate_tile() {
+        let mut buffer = ImageBuffer::new(3, 1, 1, 1).unwrap();
+        buffer.set_pixel(0, 0, 0, 0, 0, 255);
+        buffer.set_pixel(1, 0, 100, 50, 20, 255);
+        buffer.set_pixel(2, 0, 30, 60, 90, 255);
+        let before = buffer.data.clone();
+
+        buffer.pixelate_tile(0, 0, 1).unwrap();
+        assert_eq!(buffer.data, before);
+
+        // The first 2x1 block averages two pixels; the partial block at the edge keeps its one
+        buffer.pixelate_tile(0, 0, 2).unwrap();
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![50, 25, 10, 255]));
+        assert_eq!(buffer.get_pixel(1, 0), Some(vec![50, 25, 10, 255]));
+        assert_eq!(buffer.get_pixel(2, 0), Some(vec![30, 60, 90, 255]));
+    }
+
+    #[test]
+    fn test_threshold_tile() {
+        let mut buffer = ImageBuffer::new(2, 2, 2, 1).unwrap();
+        buffer
+            .fill_tile_with_color(0, 0, 128, 128, 128, 200)
+            .unwrap();
+        buffer
+            .fill_tile_with_color(1, 0, 129, 129, 129, 255)
+            .unwrap();
+
+        buffer.threshold_tile(0, 0, 128).unwrap();
+        buffer.threshold_tile(1, 0, 128).unwrap();
+        assert!(buffer
+            .read_tile_region(0, 0)
+            .chunks_exact(4)
+            .all(|p| p == [0, 0, 0, 200]));
+        assert!(buffer
+            .read_tile_region(1, 0)
+            .chunks_exact(4)
+            .all(|p| p == [255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn test_empty_tiles() {
+        let png = encode_png(&[0, 0, 0, 255].repeat(4), 2, 2).unwrap();
+        let mut buffer = ImageBuffer::new(2, 2, 2, 2).unwrap();
+        assert_eq!(buffer.empty_tile_count(), 4);
+        assert_eq!(buffer.next_empty_tile(), Some(vec![0, 0]));
+
+        buffer.load_image_from_bytes(&png, 0, 0).unwrap();
+        buffer.load_image_from_bytes(&png, 0, 1).unwrap();
+        assert_eq!(buffer.empty_tile_count(), 2);
+        assert_eq!(buffer.next_empty_tile(), Some(vec![1, 0]));
+
+        buffer.load_image_from_bytes(&png, 1, 0).unwrap();
+        buffer.load_image_from_bytes(&png, 1, 1).unwrap();
+        assert_eq!(buffer.empty_tile_count(), 0);
+        assert_eq!(buffer.next_empty_tile(), None);
+    }
+
+    #[test]
+    fn test_auto_place_image() {
+        let png = encode_png(&[0, 0, 0, 255].repeat(4), 2, 2).unwrap();
+        let mut buffer = ImageBuffer::new(2, 2, 2, 2).unwrap();
+        buffer.load_image_from_bytes(&png, 0, 0).unwrap();
+
+        assert_eq!(buffer.auto_place_image(&png, 1.0).unwrap(), vec![1, 0]);
+        assert_eq!(buffer.auto_place_image(&png, 1.0).unwrap(), vec![0, 1]);
+        assert!(buffer.is_tile_loaded(1, 0) && buffer.is_tile_loaded(0, 1));
+    }
+
+    #[test]
+    fn test_fill_tile_gradient() {
+        let mut buffer = ImageBuffer::new(3, 2, 1, 1).unwrap();
+        buffer
+            .fill_tile_gradient(0, 0, 0, 0, 0, 0, 200, 100, 50, 255, false)
+            .unwrap();
+        assert!(buffer.is_tile_loaded(0, 0));
+        assert_eq!(buffer.get_pixel(0, 1), Some(vec![0, 0, 0, 0]));
+        assert_eq!(buffer.get_pixel(1, 1), Some(vec![100, 50, 25, 128]));
+        assert_eq!(buffer.get_pixel(2, 0), Some(vec![200, 100, 50, 255]));
+
+        buffer
+            .fill_tile_gradient(0, 0, 0, 0, 0, 255, 255, 255, 255, 255, true)
+            .unwrap();
+        assert_eq!(buffer.get_pixel(2, 0), Some(vec![0, 0, 0, 255]));
+        assert_eq!(buffer.get_pixel(2, 1), Some(vec![255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn test_edge_detect_tile() {
+        let mut buffer = ImageBuffer::new(4, 4, 2, 1).unwrap();
+        buffer
+            .fill_tile_with_color(0, 0, 90, 140, 200, 180)
+            .unwrap();
+        buffer.edge_detect_tile(0, 0).unwrap();
+        assert!(buffer
+            .read_tile_region(0, 0)
+            .chunks_exact(4)
+            .all(|p| p == [0, 0, 0, 180]));
+
+        // A vertical black/white boundary lights up next to the edge only
+        buffer.fill_tile_with_color(1, 0, 0, 0, 0, 255).unwrap();
+        for y in 0..4 {
+            buffer.set_pixel(6, y, 255, 255, 255, 255);
+            buffer.set_pixel(7, y, 255, 255, 255, 255);
+        }
+        buffer.edge_detect_tile(1, 0).unwrap();
+        assert_eq!(buffer.get_pixel(4, 2), Some(vec![0, 0, 0, 255]));
+        assert_eq!(buffer.get_pixel(5, 2), Some(vec![255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn test_is_svg() {
+        assert!(is_svg(b"<svg xmlns='http://www.w3.org/2000/svg'/>"));
+        assert!(is_svg(b"\xef\xbb\xbf  <?xml version='1.0'?>\n<svg/>"));
+        assert!(!is_svg(b"<html><body/></html>"));
+        let png = encode_png(&[0, 0, 0, 255], 1, 1).unwrap();
+        assert!(!is_svg(&png));
+    }
+
+    #[cfg(feature = "svg")]
+    #[test]
+    fn test_load_svg_at_tile_resolution() {
+        // A 4x2 document, half red and half blue, rasterized straight at 16x8
+        let svg = br#"<svg xmlns="http://www.w3.org/2000/svg" width="4" height="2">
+            <rect width="2" height="2" fill="red"/>
+            <rect x="2" width="2" height="2" fill="blue"/>
+        </svg>"#;
+        let mut buffer = ImageBuffer::new(16, 16, 1, 1).unwrap();
+        buffer.load_image_from_bytes(svg, 0, 0).unwrap();
+
+        // Sharp edge between the halves, with no resampling blur
+        assert_eq!(buffer.get_pixel(7, 0), Some(vec![255, 0, 0, 255]));
+        assert_eq!(buffer.get_pixel(8, 0), Some(vec![0, 0, 255, 255]));
+        assert_eq!(buffer.get_pixel(15, 7), Some(vec![0, 0, 255, 255]));
+    }
+
+    #[test]
+    fn test_load_gif_frame() {
+        use image::codecs::gif::GifEncoder;
+
+        let frames = [[255, 0, 0, 255], [0, 0, 255, 255]]
+            .map(|color| image::Frame::new(image::RgbaImage::from_pixel(2, 2, image::Rgba(color))));
+        let mut gif = Vec::new();
+        GifEncoder::new(&mut gif).encode_frames(frames).unwrap();
+
+        let mut buffer = ImageBuffer::new(2, 2, 3, 1).unwrap();
+        buffer.load_image_from_bytes(&gif, 0, 0).unwrap();
+        buffer.load_gif_frame(&gif, 1, 0, 1, 1.0).unwrap();
+        buffer.load_gif_frame(&gif, 2, 0, 99, 1.0).unwrap();
+
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![255, 0, 0, 255]));
+        assert_eq!(buffer.get_pixel(2, 0), Some(vec![0, 0, 255, 255]));
+        assert_eq!(buffer.get_pixel(4, 0), Some(vec![0, 0, 255, 255]));
+    }
+
+    #[test]
+    fn test_auto_filter_for_large_enlargements() {
+        assert_eq!(auto_filter(0.5), ResizeFilter::Lanczos3);
+        assert_eq!(auto_filter(LANCZOS_MAX_ENLARGEMENT), ResizeFilter::Lanczos3);
+        assert_eq!(auto_filter(8.0), ResizeFilter::Triangle);
+
+        // An 8x enlargement matches an explicit Triangle resize, not the Lanczos3 one
+        let src: Vec<u8> = (0..16)
+            .flat_map(|i| [i * 16, 0, 255 - i * 16, 255])
+            .collect();
+        let png = encode_png(&src, 4, 4).unwrap();
+        let mut auto = ImageBuffer::new(32, 32, 1, 1).unwrap();
+        auto.load_image_from_bytes(&png, 0, 0).unwrap();
+        let mut triangle = ImageBuffer::new(32, 32, 1, 1).unwrap();
+        triangle
+            .load_image_from_bytes_with_filter(&png, 0, 0, 1.0, ResizeFilter::Triangle)
+            .unwrap();
+        assert_eq!(auto.data, triangle.data);
+
+        // The offset and mode loaders pick the same filter
+        let mut offset = ImageBuffer::new(32, 32, 1, 1).unwrap();
+        offset
+            .load_image_from_bytes_with_scale_and_offset(&png, 0, 0, 1.0, 0, 0)
+            .unwrap();
+        assert_eq!(offset.data, triangle.data);
+        let mut fit = ImageBuffer::new(32, 32, 1, 1).unwrap();
+        fit.load_image_from_bytes_with_mode(&png, 0, 0, ScaleMode::Fit)
+            .unwrap();
+        assert_eq!(fit.data, triangle.data);
+
+        let small = DynamicImage::ImageRgba8(image::RgbaImage::new(4, 2));
+        assert_eq!(fit_filter(&small, 32, 32), ResizeFilter::Triangle);
+        assert_eq!(fit_filter(&small, 16, 16), ResizeFilter::Lanczos3);
+        assert_eq!(cover_filter(&small, 16, 16), ResizeFilter::Triangle);
+    }
+
+    #[test]
+    fn test_get_data() {
+        let mut buffer = ImageBuffer::new(2, 2, 1, 1).unwrap();
+        buffer.fill_tile_with_color(0, 0, 1, 2, 3, 4).unwrap();
+        let data = buffer.get_data();
+        assert_eq!(data.len(), buffer.data_len());
+        assert_eq!(data, [1, 2, 3, 4].repeat(4));
+    }
+
+    #[test]
+    fn test_get_tile_transform() {
+        let png = encode_png(&[0, 0, 0, 255].repeat(4), 2, 2).unwrap();
+        let mut buffer = ImageBuffer::new(4, 4, 2, 1).unwrap();
+        assert_eq!(buffer.get_tile_transform(0, 0), None);
+
+        buffer
+            .load_image_from_bytes_with_scale_and_offset(&png, 0, 0, 1.5, -2, 3)
+            .unwrap();
+        assert_eq!(buffer.get_tile_transform(0, 0), Some(vec![1.5, -2.0, 3.0]));
+
+        buffer.rescale_tile(0, 0, 2.0).unwrap();
+        buffer
+            .load_image_from_bytes_with_scale(&png, 1, 0, 0.5)
+            .unwrap();
+        assert_eq!(buffer.get_tile_transform(0, 0), Some(vec![2.0, -2.0, 3.0]));
+        assert_eq!(buffer.get_tile_transform(1, 0), Some(vec![0.5, 0.0, 0.0]));
+    }
+
+    #[test]
+    fn test_reset_tile_transform() {
+        let src: Vec<u8> = (0..24)
+            .flat_map(|i| [i * 10, 100, 255 - i * 10, 255])
+            .collect();
+        let png = encode_png(&src, 6, 4).unwrap();
+        let mut fresh = ImageBuffer::new(8, 8, 1, 1).unwrap();
+        fresh.load_image_from_bytes(&png, 0, 0).unwrap();
+
+        let mut buffer = ImageBuffer::new(8, 8, 1, 1).unwrap();
+        buffer
+            .load_image_from_bytes_with_scale_and_offset(&png, 0, 0, 1.7, 3, -2)
+            .unwrap();
+        buffer.invert_tile(0, 0).unwrap();
+        buffer.reset_tile_transform(0, 0).unwrap();
+
+        assert_eq!(buffer.data, fresh.data);
+        assert_eq!(buffer.get_tile_transform(0, 0), Some(vec![1.0, 0.0, 0.0]));
+        assert!(!buffer.is_tile_modified(0, 0));
+    }
+
+    #[test]
+    fn test_downscale_only_mode() {
+        let small = encode_png(&[200, 0, 0, 255].repeat(4), 2, 2).unwrap();
+        let large = encode_png(&[0, 0, 200, 255].repeat(32), 8, 4).unwrap();
+        let mut buffer = ImageBuffer::new(4, 4, 2, 1).unwrap();
+
+        // The 2x2 image stays 2x2, centered
+        buffer
+            .load_image_from_bytes_with_mode(&small, 0, 0, ScaleMode::DownscaleOnly)
+            .unwrap();
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![255, 255, 255, 255]));
+        assert_eq!(buffer.get_pixel(1, 1), Some(vec![200, 0, 0, 255]));
+        assert_eq!(buffer.get_pixel(2, 2), Some(vec![200, 0, 0, 255]));
+        assert_eq!(buffer.get_pixel(3, 3), Some(vec![255, 255, 255, 255]));
+
+        // The 8x4 image shrinks to fit as 4x2, centered vertically
+        buffer
+            .load_image_from_bytes_with_mode(&large, 1, 0, ScaleMode::DownscaleOnly)
+            .unwrap();
+        assert_eq!(buffer.get_pixel(4, 0), Some(vec![255, 255, 255, 255]));
+        assert_eq!(buffer.get_pixel(4, 1), Some(vec![0, 0, 200, 255]));
+        assert_eq!(buffer.get_pixel(7, 2), Some(vec![0, 0, 200, 255]));
+    }
+
+    #[test]
+    fn test_load_image_from_bytes_timed() {
+        let png = encode_png(&[0, 200, 0, 255].repeat(4), 2, 2).unwrap();
+        let mut buffer = ImageBuffer::new(4, 4, 1, 1).unwrap();
+        let elapsed = buffer.load_image_from_bytes_timed(&png, 0, 0, 1.0).unwrap();
+        assert!(elapsed >= 0.0 && elapsed.is_finite());
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![0, 200, 0, 255]));
+    }
+
+    #[test]
+    fn test_subpixel_offset_blends_neighbours() {
+        let mut source = Vec::new();
+        for _ in 0..4 {
+            for x in 0..4u8 {
+                source.extend_from_slice(&[x * 60, 0, 255 - x * 60, 255]);
+            }
+        }
+        let png = encode_png(&source, 4, 4).unwrap();
+
+        let mut whole = ImageBuffer::new(4, 4, 1, 1).unwrap();
+        whole
+            .load_image_from_bytes_with_scale_and_offset(&png, 0, 0, 1.0, 0, 0)
+            .unwrap();
+        let mut unshifted = ImageBuffer::new(4, 4, 1, 1).unwrap();
+        unshifted
+            .load_image_from_bytes_with_subpixel_offset(&png, 0, 0, 1.0, 0.0, 0.0, true)
+            .unwrap();
+        assert_eq!(unshifted.get_data(), whole.get_data());
+
+        // Without the flag, fractional offsets snap to whole pixels
+        let mut snapped = ImageBuffer::new(4, 4, 1, 1).unwrap();
+        snapped
+            .load_image_from_bytes_with_subpixel_offset(&png, 0, 0, 1.0, 0.2, 0.0, false)
+            .unwrap();
+        assert_eq!(snapped.get_data(), whole.get_data());
+
+        // A half-pixel shift right lands each pixel halfway between its neighbours
+        let mut shifted = ImageBuffer::new(4, 4, 1, 1).unwrap();
+        shifted
+            .load_image_from_bytes_with_subpixel_offset(&png, 0, 0, 1.0, 0.5, 0.0, true)
+            .unwrap();
+        let left = whole.get_pixel(1, 2).unwrap();
+        let right = whole.get_pixel(2, 2).unwrap();
+        let blended = shifted.get_pixel(2, 2).unwrap();
+        for channel in 0..4 {
+            let expected = (left[channel] as f32 + right[channel] as f32) / 2.0;
+            assert!((blended[channel] as f32 - expected).abs() <= 0.5);
+        }
+        assert_eq!(shifted.get_tile_transform(0, 0).unwrap()[1..], [1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_offset_outside_modes() {
+        let png = encode_png(&[0, 200, 0, 255].repeat(4), 2, 2).unwrap();
+        let mut buffer = ImageBuffer::new(4, 4, 3, 1).unwrap();
+        buffer
+            .load_image_from_bytes_with_offset_outside(
+                &png,
+                0,
+                0,
+                0.5,
+                -1,
+                0,
+                OutsideMode::Background,
+            )
+            .unwrap();
+        buffer
+            .load_image_from_bytes_with_offset_outside(
+                &png,
+                1,
+                0,
+                0.5,
+                -1,
+                0,
+                OutsideMode::ClampEdge,
+            )
+            .unwrap();
+        buffer
+            .load_image_from_bytes_with_offset_outside(
+                &png,
+                2,
+                0,
+                0.5,
+                -1,
+                0,
+                OutsideMode::Transparent,
+            )
+            .unwrap();
+
+        assert_eq!(buffer.get_pixel(3, 0), Some(vec![255, 255, 255, 255]));
+        assert_eq!(buffer.get_pixel(7, 0), Some(vec![0, 200, 0, 255]));
+        assert_eq!(buffer.get_pixel(11, 0), Some(vec![0, 0, 0, 0]));
+
+        // The mode sticks to the tile across pans
+        buffer.reoffset_tile(1, 0, 1, 1).unwrap();
+        assert_eq!(buffer.get_pixel(4, 0), Some(vec![0, 200, 0, 255]));
+    }
+
+    #[test]
+    fn test_load_image_autotrim() {
+        // A 2x2 red block inside a near-white 6x6 margin
+        let mut pixels = Vec::new();
+        for y in 0..6 {
+            for x in 0..6 {
+                if (2..4).contains(&x) && (2..4).contains(&y) {
+                    pixels.extend_from_slice(&[255, 0, 0, 255]);
+                } else {
+                    pixels.extend_from_slice(&[250 + ((x + y) % 2) as u8, 252, 255, 255]);
+                }
+            }
+        }
+        let png = encode_png(&pixels, 6, 6).unwrap();
+
+        let mut buffer = ImageBuffer::new(2, 2, 2, 1).unwrap();
+        buffer.load_image_autotrim(&png, 0, 0, 1.0, 8).unwrap();
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![255, 0, 0, 255]));
+
+        // A tolerance below the margin's variation finds no border and loads the whole image
+        buffer.load_image_autotrim(&png, 1, 0, 1.0, 0).unwrap();
+        assert_ne!(buffer.get_pixel(2, 0), Some(vec![255, 0, 0, 255]));
+
+        let uniform = encode_png(&[10, 20, 30, 255].repeat(4), 2, 2).unwrap();
+        buffer.load_image_autotrim(&uniform, 1, 0, 1.0, 8).unwrap();
+        assert_eq!(buffer.get_pixel(3, 1), Some(vec![10, 20, 30, 255]));
+    }
+
+    #[test]
+    fn test_cmyk_jpeg_decodes_to_rgb() {
+        // 8x8 of pure red in CMYK (no cyan, full magenta and yellow, no black)
+        let cmyk = [0u8, 255, 255, 0].repeat(64);
+        for color_type in [
+            jpeg_encoder::ColorType::Cmyk,
+            jpeg_encoder::ColorType::CmykAsYcck,
+        ] {
+            let mut jpeg = Vec::new();
+            jpeg_encoder::Encoder::new(&mut jpeg, 100)
+                .encode(&cmyk, 8, 8, color_type)
+                .unwrap();
+
+            let mut buffer = ImageBuffer::new(8, 8, 1, 1).unwrap();
+            buffer.load_image_from_bytes(&jpeg, 0, 0).unwrap();
+            let pixel = buffer.get_pixel(4, 4).unwrap();
+            let expected = [255, 0, 0, 255];
+            assert!(
+                pixel
+                    .iter()
+                    .zip(expected)
+                    .all(|(&got, want)| got.abs_diff(want) <= 4),
+                "{:?} decoded to {:?}",
+                color_type,
+                pixel
+            );
+        }
+    }
+
+    #[test]
+    fn test_premultiply_alpha_round_trip() {
+        let mut buffer = ImageBuffer::new(2, 1, 2, 1).unwrap();
+        buffer.set_pixel(0, 0, 200, 100, 50, 128);
+        buffer.set_pixel(1, 0, 10, 20, 30, 0);
+        let original = buffer.get_data();
+
+        buffer.premultiply_alpha();
+        buffer.premultiply_alpha();
+        assert!(buffer.is_alpha_premultiplied());
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![100, 50, 25, 128]));
+        assert_eq!(buffer.get_pixel(1, 0), Some(vec![0, 0, 0, 0]));
+
+        // Loads while premultiplied follow the buffer's convention
+        let png = encode_png(&[200, 100, 50, 128].repeat(4), 2, 2).unwrap();
+        buffer.load_image_from_bytes(&png, 1, 0).unwrap();
+        assert_eq!(buffer.get_pixel(2, 0), Some(vec![100, 50, 25, 128]));
+
+        buffer.unpremultiply_alpha();
+        assert!(!buffer.is_alpha_premultiplied());
+        let restored = buffer.get_pixel(0, 0).unwrap();
+        for (got, want) in restored.iter().zip(&original[..4]) {
+            assert!(got.abs_diff(*want) <= 1);
+        }
+        assert_eq!(buffer.get_pixel(1, 0), Some(vec![0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_premultiplied_rotations_do_not_darken() {
+        let png = encode_png(&[200, 100, 50, 128].repeat(4), 2, 2).unwrap();
+        let mut buffer = ImageBuffer::new(2, 2, 1, 1).unwrap();
+        buffer.premultiply_alpha();
+        buffer.load_image_from_bytes(&png, 0, 0).unwrap();
+
+        buffer.rotate_tile(0, 0, 1).unwrap();
+        buffer.rotate_tile(0, 0, 1).unwrap();
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![100, 50, 25, 128]));
+
+        buffer.rotate_canvas(1);
+        assert!(buffer.is_alpha_premultiplied());
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![100, 50, 25, 128]));
+    }
+
+    #[test]
+    fn test_premultiplied_fills_follow_convention() {
+        let mut buffer = ImageBuffer::new(2, 2, 2, 1).unwrap();
+        buffer.premultiply_alpha();
+
+        buffer
+            .fill_tile_with_color(0, 0, 200, 100, 50, 128)
+            .unwrap();
+        assert_eq!(buffer.get_pixel(1, 1), Some(vec![100, 50, 25, 128]));
+
+        buffer
+            .fill_tile_gradient(1, 0, 200, 100, 50, 128, 200, 100, 50, 128, false)
+            .unwrap();
+        assert_eq!(buffer.get_pixel(3, 1), Some(vec![100, 50, 25, 128]));
+    }
+
+    #[test]
+    fn test_premultiplied_collage_follows_convention() {
+        let png = encode_png(&[200, 100, 50, 128].repeat(4), 2, 2).unwrap();
+        let mut buffer = ImageBuffer::new(2, 2, 1, 1).unwrap();
+        buffer.premultiply_alpha();
+
+        buffer.begin_tile_collage(0, 0, 1, 1).unwrap();
+        buffer.add_collage_image(&png).unwrap();
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![100, 50, 25, 128]));
+    }
+
+    #[test]
+    fn test_premultiplied_blend_follows_convention() {
+        let png = encode_png(&[200, 100, 50, 128].repeat(4), 2, 2).unwrap();
+        let mut buffer = ImageBuffer::new(2, 2, 1, 1).unwrap();
+        buffer.premultiply_alpha();
+
+        // Over transparent black the blend leaves the premultiplied source as is
+        buffer.fill_tile_with_color(0, 0, 0, 0, 0, 0).unwrap();
+        buffer.load_image_from_bytes_blend(&png, 0, 0, 1.0).unwrap();
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![100, 50, 25, 128]));
+
+        // Over opaque white it matches the straight-alpha result
+        buffer
+            .fill_tile_with_color(0, 0, 255, 255, 255, 255)
+            .unwrap();
+        buffer.load_image_from_bytes_blend(&png, 0, 0, 1.0).unwrap();
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![227, 177, 152, 255]));
+    }
+
+    #[test]
+    fn test_premultiplied_overlays_follow_convention() {
+        let mut buffer = ImageBuffer::new(2, 2, 2, 1).unwrap();
+        buffer.premultiply_alpha();
+        buffer.fill_tile_with_color(0, 0, 0, 0, 0, 0).unwrap();
+        buffer.fill_tile_with_color(1, 0, 0, 0, 0, 0).unwrap();
+
+        buffer.draw_caption_bar(0, 0, 1, 200, 100, 50, 128).unwrap();
+        assert_eq!(buffer.get_pixel(0, 1), Some(vec![100, 50, 25, 128]));
+
+        // A straight-alpha buffer composited on top is converted on the way in
+        let mut other = ImageBuffer::new(1, 1, 1, 1).unwrap();
+        other.set_pixel(0, 0, 200, 100, 50, 128);
+        buffer.composite_buffer(&other, 2, 0);
+        assert_eq!(buffer.get_pixel(2, 0), Some(vec![100, 50, 25, 128]));
+    }
+
+    #[test]
+    fn test_premultiplied_background_and_clears() {
+        let expected = Some(vec![100, 50, 25, 128]);
+        let mut buffer = ImageBuffer::with_gutter(2, 2, 2, 1, 1).unwrap();
+        buffer.premultiply_alpha();
+        buffer.set_background_color(200, 100, 50, 128);
+
+        buffer.fill_background();
+        assert_eq!(buffer.get_pixel(0, 0), expected);
+        assert_eq!(buffer.get_pixel(2, 0), expected);
+
+        buffer.fill_tile_with_color(0, 0, 0, 0, 0, 255).unwrap();
+        buffer.clear_tile(0, 0).unwrap();
+        assert_eq!(buffer.get_pixel(1, 1), expected);
+
+        buffer.fill_tile_with_color(1, 0, 0, 0, 0, 255).unwrap();
+        buffer.clear_all_tiles();
+        assert_eq!(buffer.get_pixel(4, 1), expected);
+
+        // Grid resizes clear through clear_all_tiles too
+        buffer.resize_grid(2, 2).unwrap();
+        assert_eq!(buffer.get_pixel(0, 4), expected);
+        assert!(buffer.is_alpha_premultiplied());
+
+        buffer.fill_tile_with_color(0, 0, 0, 0, 0, 255).unwrap();
+        buffer.crop_tile(0, 0, 0, 0, 1, 1).unwrap();
+        assert_eq!(buffer.get_pixel(1, 1), expected);
+
+        assert_eq!(buffer.flood_fill(0, 0, 200, 100, 50, 128, 0), 1);
+        assert_eq!(buffer.get_pixel(0, 0), expected);
+    }
+
+    #[test]
+    fn test_premultiplied_alpha_edits_keep_color_within_alpha() {
+        let mut buffer = ImageBuffer::new(4, 4, 3, 1).unwrap();
+        buffer.premultiply_alpha();
+        for col in 0..3 {
+            buffer
+                .fill_tile_with_color(col, 0, 200, 100, 50, 255)
+                .unwrap();
+        }
+
+        buffer.set_tile_opacity(0, 0, 0.5).unwrap();
+        assert_eq!(buffer.get_pixel(0, 0), Some(vec![100, 50, 25, 128]));
+
+        // The key matches the straight color even though the pixel is stored premultiplied
+        buffer.set_tile_opacity(1, 0, 0.5).unwrap();
+        buffer.remove_color_key(1, 0, 200, 100, 50, 2).unwrap();
+        assert_eq!(buffer.get_pixel(4, 0), Some(vec![0, 0, 0, 0]));
+
+        buffer.apply_rounded_corners(2, 0, 2).unwrap();
+        let corner = buffer.get_pixel(8, 0).unwrap();
+        assert!(corner[3] < 255);
+        assert!(corner[..3].iter().all(|&channel| channel <= corner[3]));
+    }
+
+    #[test]
+    fn test_lock_and_unlock_tile() {
+        let png = encode_png(&[0, 0, 200, 255].repeat(4), 2, 2).unwrap();
+        let mut buffer = ImageBuffer::new(2, 2, 2, 1).unwrap();
+        buffer.load_image_from_bytes(&png, 0, 0).unwrap();
+
+        buffer.lock_tile(0, 0).unwrap();
+        assert!(buffer.is_tile_locked(0, 0));
+        assert!(!buffer.is_tile_locked(1, 0));
+        assert!(buffer.validate_tile_unlocked(1, 0).is_ok());
+
+        // Locking an empty cell adds an entry that unlocking drops again; meanwhile
+        // auto-placement skips it
+        buffer.lock_tile(1, 0).unwrap();
+        assert!(buffer.is_tile_locked(1, 0));
+        assert!(!buffer.is_tile_loaded(1, 0));
+        assert_eq!(buffer.next_empty_tile(), None);
+        buffer.unlock_tile(1, 0).unwrap();
+        assert_eq!(buffer.loaded_tiles.len(), 1);
+
+        buffer.unlock_tile(0, 0).unwrap();
+        assert!(!buffer.is_tile_locked(0, 0));
+        assert!(buffer.is_tile_loaded(0, 0));
+        buffer.clear_tile(0, 0).unwrap();
+        assert!(buffer.loaded_tiles.is_empty());
+    }
+
+    #[test]
+    fn test_flood_fill_stops_at_boundaries() {
+        // 5x5 white buffer with a near-white pixel and a black wall down column 2
+        let mut buffer = ImageBuffer::new(5, 5, 1, 1).unwrap();
+        buffer
+            .fill_tile_with_color(0, 0, 255, 255, 255, 255)
+            .unwrap();
+        for y in 0..5 {
+            buffer.set_pixel(2, y, 0, 0, 0, 255);
+        }
+        buffer.set_pixel(0, 4, 250, 250, 250, 255);
+        buffer.take_dirty_rect();
+
+        // Tolerance 0 leaves the near-white pixel alone, and the wall blocks the right side
+        assert_eq!(buffer.flood_fill(0, 0, 255, 0, 0, 255, 0), 9);
+        assert_eq!(buffer.get_pixel(1, 3), Some(vec![255, 0, 0, 255]));
+        assert_eq!(buffer.get_pixel(0, 4), Some(vec![250, 250, 250, 255]));
+        assert_eq!(buffer.get_pixel(3, 0), Some(vec![255, 255, 255, 255]));
+        assert_eq!(buffer.take_dirty_rect(), Some(vec![0, 0, 2, 5]));
+
+        // Filling with a color that still matches the seed terminates
+        assert_eq!(buffer.flood_fill(4, 4, 254, 254, 254, 255, 8), 10);
+        assert_eq!(buffer.flood_fill(5, 0, 0, 0, 0, 0, 0), 0);
+    }
+
+    #[test]
+    fn test_flood_fill_respects_locks() {
+        let mut buffer = ImageBuffer::new(2, 2, 3, 1).unwrap();
+        for col in 0..3 {
+            buffer
+                .fill_tile_with_color(col, 0, 255, 255, 255, 255)
+                .unwrap();
+        }
+        buffer.lock_tile(1, 0).unwrap();
+
+        // The locked middle tile walls off the right one and keeps its pixels
+        assert_eq!(buffer.flood_fill(0, 0, 255, 0, 0, 255, 0), 4);
+        assert_eq!(buffer.get_pixel(2, 0), Some(vec![255, 255, 255, 255]));
+        assert_eq!(buffer.get_pixel(4, 0), Some(vec![255, 255, 255, 255]));
+        assert_eq!(buffer.flood_fill(3, 1, 255, 0, 0, 255, 0), 0);
+        assert!(buffer.is_tile_modified(0, 0));
+        assert!(!buffer.is_tile_modified(2, 0));
+    }
+
+    // The lock error is a JsValue, which can only be built on wasm
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen_test]
+    fn test_move_from_locked_tile_changes_nothing() {
+        let png = encode_png(&[0, 0, 200, 255].repeat(4), 2, 2).unwrap();
+        let mut buffer = ImageBuffer::new(2, 2, 2, 1).unwrap();
+        buffer.load_image_from_bytes(&png, 0, 0).unwrap();
+        buffer.lock_tile(0, 0).unwrap();
+        let before = buffer.get_data();
+
+        assert!(buffer.move_tile(0, 0, 1, 0).is_err());
+        assert_eq!(buffer.get_data(), before);
+        assert!(buffer.is_tile_loaded(0, 0));
+        assert!(!buffer.is_tile_loaded(1, 0));
+    }
+
+    // The lock error is a JsValue, which can only be built on wasm
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen_test]
+    fn test_collage_add_into_locked_tile_changes_nothing() {
+        let mut buffer = ImageBuffer::new(8, 4, 1, 1).unwrap();
+        buffer.begin_tile_collage(0, 0, 2, 1).unwrap();
+        buffer.lock_tile(0, 0).unwrap();
+        let before = buffer.get_data();
+
+        assert!(buffer
+            .add_collage_image(&solid_png(4, 4, [200, 0, 0, 255]))
+            .is_err());
+        assert_eq!(buffer.get_data(), before);
     }
 }